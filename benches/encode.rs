@@ -0,0 +1,50 @@
+// scaling of encode_parallel() w/ the number of threads, and the chunk cache:
+// cargo bench --bench encode --features parallel
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use common::TAYLOR_SWIFT;
+use tokenizer::observer::NoObserver;
+use tokenizer::training::Verbosity;
+use tokenizer::{BpeTokenizer, Tokenizer, TrainerConfig};
+
+const ROUNDS: usize = 5;
+
+/// runs 'encode' over the text ROUNDS times and returns the throughput in MB/s
+fn bench(text: &str, encode: impl Fn(&str) -> usize) -> f64 {
+    black_box(encode(text));    // warm up
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(encode(black_box(text)));
+    }
+    (text.len() * ROUNDS) as f64 / 1e6 / start.elapsed().as_secs_f64()
+}
+
+fn main() {
+    let config = TrainerConfig { vocab_size: 4096, verbosity: Verbosity::Quiet, ..TrainerConfig::default() };
+    let mut tokenizer = BpeTokenizer::new();
+    tokenizer.train_with_config(TAYLOR_SWIFT, &config, &mut NoObserver).unwrap();
+
+    let text = TAYLOR_SWIFT.repeat(20);
+    println!("encoding {:.1} MB w/ {} tokens", text.len() as f64 / 1e6, tokenizer.vocab_size());
+    let serial = bench(&text, |text| tokenizer.encode(text).unwrap().len());
+    println!("{:<12} {:>8.1} MB/s", "serial", serial);
+
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut threads = 1;
+    while threads <= cores {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+        let parallel = pool.install(|| bench(&text, |text| tokenizer.encode_parallel(text).unwrap().len()));
+        println!("{:<12} {:>8.1} MB/s  ({:.2}x serial)", format!("{} threads", threads), parallel, parallel / serial);
+        threads *= 2;
+    }
+
+    tokenizer.set_cache_capacity(100_000);
+    let cached = bench(&text, |text| tokenizer.encode(text).unwrap().len());
+    let stats = tokenizer.cache_stats().unwrap();
+    println!("{:<12} {:>8.1} MB/s  ({:.1}% hits)", "cached", cached, stats.hit_rate() * 100.0);
+}
@@ -0,0 +1,46 @@
+// throughput of the GPT-4 pre-tokenizers: cargo bench --bench split [--features fancy-regex]
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use common::TAYLOR_SWIFT;
+use tokenizer::pretokenizer::{PreTokenizer, RegexPreTokenizer, GPT4_SPLIT_PATTERN};
+use tokenizer::scanner::{gpt4_chunks, Gpt4Scanner};
+
+const ROUNDS: usize = 20;
+
+/// runs 'split' over the text ROUNDS times and prints the throughput
+fn bench(name: &str, text: &str, split: impl Fn(&str) -> usize) {
+    let chunks = split(text);   // warm up
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(split(black_box(text)));
+    }
+    let seconds = start.elapsed().as_secs_f64();
+    let megabytes = (text.len() * ROUNDS) as f64 / 1e6;
+    println!("{:<28} {:>8.1} MB/s  ({} chunks)", name, megabytes / seconds, chunks);
+}
+
+fn main() {
+    // ~1MB of English plus about the same of mixed scripts
+    let english = TAYLOR_SWIFT.repeat(5);
+    let mixed = "Ωμέγα Привет, 東京 🦀 naïve café — it's 2024!\n".repeat(english.len() / 60);
+
+    for (label, text) in [("english", &english), ("mixed", &mixed)] {
+        println!("{} ({:.1} MB)", label, text.len() as f64 / 1e6);
+        let regex: Box<dyn PreTokenizer> = Box::new(RegexPreTokenizer::new(GPT4_SPLIT_PATTERN).unwrap());
+        bench("  regex crate (owned chunks)", text, |text| regex.split(text).len());
+        bench("  regex crate (slices)", text, |text| regex.chunks(text).count());
+        #[cfg(feature = "fancy-regex")]
+        {
+            let fancy: Box<dyn PreTokenizer> =
+                Box::new(tokenizer::pretokenizer::FancyRegexPreTokenizer::new(GPT4_SPLIT_PATTERN).unwrap());
+            bench("  fancy-regex (slices)", text, |text| fancy.chunks(text).count());
+        }
+        bench("  scanner (owned chunks)", text, |text| Gpt4Scanner.split(text).len());
+        bench("  scanner (slices)", text, |text| gpt4_chunks(text).count());
+    }
+}
@@ -0,0 +1,133 @@
+use crate::cache::ChunkCache;
+use crate::observer::{NoObserver, TrainingObserver};
+use crate::error::Result;
+use crate::merge_heap::LONG_CHUNK_LEN;
+use crate::special::{AllowedSpecial, Segment};
+use crate::stream::StreamDecoder;
+use crate::training::{self, TrainerConfig, Verbosity, Vocabulary};
+use crate::Tokenizer;
+
+/// byte pair encoding tokenizer built around a trained Vocabulary
+#[derive(Default)]
+pub struct BpeTokenizer {
+    pub vocab: Vocabulary,
+    pub(crate) cache: Option<ChunkCache>,   // off until set_cache_capacity(), see cache.rs
+}
+
+impl BpeTokenizer {
+
+    /// an untrained tokenizer that maps text to raw bytes
+    pub fn new() -> BpeTokenizer {
+        BpeTokenizer::default()
+    }
+
+    /// wrap an already trained Vocabulary
+    pub fn from_vocabulary(vocab: Vocabulary) -> BpeTokenizer {
+        BpeTokenizer { vocab, cache: None }
+    }
+
+    /// train w/ every knob of the TrainerConfig available while 'observer' follows along
+    pub fn train_with_config(&mut self, text: &str, config: &TrainerConfig, observer: &mut dyn TrainingObserver) -> Result<()> {
+        self.vocab = training::train_tokenizer(text, config, observer)?;
+        self.clear_cache();
+        Ok(())
+    }
+
+    /// encode() that also emits the special tokens 'allowed' lets through
+    pub fn encode_special(&self, text: &str, allowed: &AllowedSpecial) -> Result<Vec<u32>> {
+        let mut ids = Vec::new();
+        for segment in self.vocab.split_special(text, allowed)? {
+            match segment {
+                Segment::Special(idx) => ids.push(idx),
+                Segment::Text(part) => ids.extend(self.encode_verbose(part, false)?),
+            }
+        }
+        Ok(ids)
+    }
+
+    /// a decoder for ids that arrive one at a time (e.g. while generating)
+    pub fn stream_decoder(&self) -> StreamDecoder<'_> {
+        StreamDecoder::new(&self.vocab)
+    }
+
+    /// encode() w/ the option to print each replacement as it happens
+    pub fn encode_verbose(&self, text: &str, verbose: bool) -> Result<Vec<u32>> {
+        // each chunk of the pre-tokenizer is encoded on its own, then results are joined
+        let text = self.vocab.normalizer.normalize(text);
+        let mut encoded_text: Vec<u32> = Vec::new();
+        for chunk in self.vocab.pre_tokenizer.chunks(&text) {
+            encoded_text.extend(self.encode_chunk(chunk.as_bytes(), verbose));
+        }
+        Ok(encoded_text)
+    }
+
+    /// repeatedly merges the pair w/ the lowest rank (earliest learned) until no
+    /// learned pair is left, so the result only depends on the chunk itself
+    pub fn encode_chunk(&self, chunk: &[u8], verbose: bool) -> Vec<u32> {
+        match &self.cache {
+            // a cache hit has nothing to print
+            Some(cache) if !verbose => cache.get_or_encode(chunk, || self.apply_merges(chunk, false)),
+            _ => self.apply_merges(chunk, verbose),
+        }
+    }
+
+    /// helper for encode_chunk(): the merges w/o the cache... long chunks go to
+    /// encode_chunk_linear() unless each replacement has to be printed
+    fn apply_merges(&self, chunk: &[u8], verbose: bool) -> Vec<u32> {
+        if chunk.len() > LONG_CHUNK_LEN && !verbose {
+            return self.encode_chunk_linear(chunk);
+        }
+        let vocab = &self.vocab;
+        let mut ids: Vec<u32> = chunk.iter().map(|&b| vocab.byte_token(b)).collect();
+        while ids.len() >= 2 {
+            // find the pair with the lowest merge rank
+            let lowest = ids
+                .windows(2)
+                .filter_map(|window| {
+                    let pair = (window[0], window[1]);
+                    vocab.vocab_ranks.get(&pair).map(|&new_word| (new_word, pair))
+                })
+                .min();
+            // nothing else can be merged anymore
+            let Some((new_word, (byte1, byte2))) = lowest else {
+                break;
+            };
+            // print replacements
+            if verbose {
+                let string_byte1 = vocab.stringify_word(&[byte1]);
+                let string_byte2 = vocab.stringify_word(&[byte2]);
+                let string_view = format!("{}{}", &string_byte1, &string_byte2);
+                println!("replacing {:?}, {:?} with {:?}", 
+                    string_byte1,
+                    string_byte2,
+                    string_view);
+            }
+            ids = training::merge(&ids, (byte1, byte2), new_word);
+        }
+        ids
+    }
+}
+
+impl Tokenizer for BpeTokenizer {
+
+    fn train(&mut self, text: &str, vocab_size: u32, verbose: bool) -> Result<()> {
+        let config = TrainerConfig {
+            vocab_size,
+            verbosity: if verbose { Verbosity::Merges } else { Verbosity::Summary },
+            ..TrainerConfig::default()
+        };
+        self.train_with_config(text, &config, &mut NoObserver)
+    }
+
+    fn encode(&self, text: &str) -> Result<Vec<u32>> {
+        self.encode_verbose(text, false)
+    }
+
+    fn decode(&self, ids: &[u32]) -> String {
+        self.vocab.decode_lossy(ids)
+    }
+
+    fn vocab_size(&self) -> usize {
+        self.vocab.len()
+    }
+}
@@ -0,0 +1,115 @@
+// most chunks of real text are common words seen over and over, so the merges of a chunk
+// are worth remembering... the cache maps chunk bytes to their ids and is shared by every
+// thread encoding w/ the tokenizer (encode_parallel() included)
+//
+// the ids only depend on the chunk and the merges, so a cache is only valid for the
+// vocabulary it was filled from: train_with_config() empties it, anything else changing
+// tokenizer.vocab should call clear_cache()
+
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use lru::LruCache;
+
+use crate::bpe::BpeTokenizer;
+
+/// chunks longer than this are rare and would take a lot of room, they are not cached
+pub const MAX_CACHED_CHUNK_LEN: usize = 256;
+
+/// least recently used chunk -> ids entries, up to a fixed number of them
+pub struct ChunkCache {
+    entries: Mutex<LruCache<Vec<u8>, Vec<u32>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// how well the cache has been doing since it was created or cleared
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// chunks currently cached
+    pub entries: usize,
+    pub capacity: usize,
+}
+
+impl CacheStats {
+
+    /// share of the lookups that were hits (0 before the first lookup)
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            lookups => self.hits as f64 / lookups as f64,
+        }
+    }
+}
+
+impl ChunkCache {
+
+    pub fn new(capacity: NonZeroUsize) -> ChunkCache {
+        ChunkCache {
+            entries: Mutex::new(LruCache::new(capacity)),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// the cached ids of 'chunk', or 'encode' run on a miss (w/o holding the lock)
+    pub fn get_or_encode(&self, chunk: &[u8], encode: impl FnOnce() -> Vec<u32>) -> Vec<u32> {
+        if chunk.len() > MAX_CACHED_CHUNK_LEN {
+            return encode();
+        }
+        if let Some(ids) = self.lock().get(chunk) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return ids.clone();
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let ids = encode();
+        self.lock().put(chunk.to_vec(), ids.clone());
+        ids
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let entries = self.lock();
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: entries.len(),
+            capacity: entries.cap().get(),
+        }
+    }
+
+    /// drops every entry and resets the statistics
+    pub fn clear(&self) {
+        self.lock().clear();
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+    }
+
+    /// helper: the entries... a panic elsewhere cannot leave them half updated, so a
+    /// poisoned lock is still fine to use
+    fn lock(&self) -> std::sync::MutexGuard<'_, LruCache<Vec<u8>, Vec<u32>>> {
+        self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+
+impl BpeTokenizer {
+
+    /// keep the ids of up to 'capacity' recently encoded chunks (0 turns the cache off)
+    pub fn set_cache_capacity(&mut self, capacity: usize) {
+        self.cache = NonZeroUsize::new(capacity).map(ChunkCache::new);
+    }
+
+    /// None when there is no cache
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().map(ChunkCache::stats)
+    }
+
+    /// forget every cached chunk, needed after the vocabulary was changed
+    pub fn clear_cache(&self) {
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
+    }
+}
@@ -0,0 +1,156 @@
+// training input too big to hold as one string: files, a directory of them, one document
+// per line or JSONL... only a block of one document is in memory at a time, and what is kept
+// is the table of distinct chunks (see count_chunks()), which grows far slower than the text
+//
+// every document is split on its own, so no chunk (and no merge) spans two documents. a
+// long document is read in blocks cut between a non-whitespace character + '\n' and a line
+// starting w/ a non-whitespace character: none of the preset patterns can match across
+// that point, so the chunks are the ones of the whole document. a longer run of whitespace
+// before the line is no such point... GPT-2's '\s+(?!\S)' leaves the last '\n' of "\n\n"
+// to the next chunk when text follows, but takes both at the end of a block. w/ any other
+// pattern a document is read whole
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use crate::bpe::BpeTokenizer;
+use crate::error::{Result, TokenizerError};
+use crate::observer::TrainingObserver;
+use crate::pretokenizer::{GPT2_SPLIT_PATTERN, GPT4_SPLIT_PATTERN, LLAMA3_SPLIT_PATTERN, O200K_SPLIT_PATTERN};
+use crate::training::{self, TrainerConfig};
+
+/// documents are handed to the pre-tokenizer in blocks of about this many bytes
+pub const BLOCK_LEN: usize = 1 << 20;
+
+/// where the training text comes from
+#[derive(Clone, Debug)]
+pub enum Corpus {
+    /// each file is a document
+    Files(Vec<PathBuf>),
+    /// every file below the directory (in sorted order, w/o following symlinks), each a document
+    Directory(PathBuf),
+    /// each line of the file is a document
+    Lines(PathBuf),
+    /// each line of the file is a JSON object whose 'text_field' string is a document
+    Jsonl { path: PathBuf, text_field: String },
+}
+
+impl Corpus {
+
+    /// calls 'block' w/ the text of every document, a block at a time when 'cut' allows it
+    fn for_each_block(&self, cut: bool, block: &mut dyn FnMut(&str)) -> Result<()> {
+        match self {
+            Corpus::Files(paths) => {
+                for path in paths {
+                    read_document(BufReader::new(File::open(path)?), cut, block)?;
+                }
+            },
+            Corpus::Directory(dir) => {
+                let mut paths = Vec::new();
+                walk(dir, &mut paths)?;
+                paths.sort();
+                for path in paths {
+                    read_document(BufReader::new(File::open(path)?), cut, block)?;
+                }
+            },
+            Corpus::Lines(path) => {
+                for line in BufReader::new(File::open(path)?).lines() {
+                    let line = line?;
+                    if !line.is_empty() {
+                        block(&line);
+                    }
+                }
+            },
+            Corpus::Jsonl { path, text_field } => {
+                for (number, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+                    let line = line?;
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    let invalid = |message: &str| TokenizerError::InvalidCorpus(
+                        format!("{} line {}: {}", path.display(), number + 1, message));
+                    let object: serde_json::Value = serde_json::from_str(&line).map_err(|err| invalid(&err.to_string()))?;
+                    match object.get(text_field) {
+                        Some(serde_json::Value::String(text)) => block(text),
+                        Some(_) => return Err(invalid(&format!("{:?} is not a string", text_field))),
+                        None => return Err(invalid(&format!("no {:?} field", text_field))),
+                    }
+                }
+            },
+        }
+        Ok(())
+    }
+}
+
+
+/// the distinct chunks of every document of the corpus w/ their occurrences, ready for
+/// training::train_from_chunk_counts() w/ the same config
+pub fn count_corpus_chunks(corpus: &Corpus, config: &TrainerConfig) -> Result<HashMap<String, u64>> {
+    let pattern = config.pre_tokenizer.pattern();
+    let cut = [GPT2_SPLIT_PATTERN, GPT4_SPLIT_PATTERN, O200K_SPLIT_PATTERN, LLAMA3_SPLIT_PATTERN].contains(&pattern);
+    let mut chunk_counts: HashMap<String, u64> = HashMap::new();
+    corpus.for_each_block(cut, &mut |text| training::add_chunk_counts(&mut chunk_counts, text, config))?;
+    Ok(chunk_counts)
+}
+
+
+impl BpeTokenizer {
+
+    /// train_with_config() on a corpus read a document at a time
+    pub fn train_from_corpus(&mut self, corpus: &Corpus, config: &TrainerConfig, observer: &mut dyn TrainingObserver) -> Result<()> {
+        training::check_special_tokens(config)?;
+        let chunk_counts = count_corpus_chunks(corpus, config)?;
+        self.vocab = training::train_from_chunk_counts(&chunk_counts, config, observer)?;
+        self.clear_cache();
+        Ok(())
+    }
+}
+
+
+/// helper for for_each_block(): one document, in blocks of at least BLOCK_LEN bytes if 'cut'
+fn read_document(mut reader: impl BufRead, cut: bool, block: &mut dyn FnMut(&str)) -> Result<()> {
+    let mut text = String::new();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        // the text so far ends in a lone '\n' (read_line() keeps it) and the line starts w/ a non-space
+        if cut && text.len() >= BLOCK_LEN && ends_in_lone_newline(&text) && line.starts_with(|c: char| !c.is_whitespace()) {
+            block(&text);
+            text.clear();
+        }
+        text.push_str(&line);
+    }
+    if !text.is_empty() {
+        block(&text);
+    }
+    Ok(())
+}
+
+
+/// helper for read_document(): the text ends in '\n' w/ no whitespace right before it
+fn ends_in_lone_newline(text: &str) -> bool {
+    text.strip_suffix('\n')
+        .and_then(|text| text.chars().next_back())
+        .is_some_and(|c| !c.is_whitespace())
+}
+
+
+/// helper for for_each_block(): every file below 'dir'... symlinks are skipped, they
+/// could lead back up the tree or out of it
+fn walk(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            walk(&entry.path(), paths)?;
+        } else if file_type.is_file() {
+            paths.push(entry.path());
+        }
+    }
+    Ok(())
+}
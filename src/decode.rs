@@ -0,0 +1,51 @@
+// ids back to text... decode_bytes() gives the exact bytes, then the caller picks how
+// to treat bytes that are not UTF-8: decode_strict() reports them, decode_lossy()
+// replaces them w/ U+FFFD like minbpe's decode() (bytes.decode("utf-8", errors="replace"))
+
+use crate::error::{Result, TokenizerError};
+use crate::training::Vocabulary;
+
+impl Vocabulary {
+
+    /// true for the raw bytes, every merged token and the registered special tokens
+    pub fn contains_token(&self, id: u32) -> bool {
+        self.token_bytes(id).is_some()
+    }
+
+    /// the exact bytes the ids stand for
+    pub fn decode_bytes(&self, ids: &[u32]) -> Result<Vec<u8>> {
+        self.decode_with_ends(ids).map(|(bytes, _)| bytes)
+    }
+
+    /// decode to a string, failing on bytes that are not valid UTF-8
+    pub fn decode_strict(&self, ids: &[u32]) -> Result<String> {
+        let (bytes, ends) = self.decode_with_ends(ids)?;
+        String::from_utf8(bytes).map_err(|err| {
+            let offset = err.utf8_error().valid_up_to();
+            // the token whose bytes contain the offset
+            let position = ends.partition_point(|&end| end <= offset);
+            TokenizerError::InvalidUtf8 { id: ids[position], position, offset }
+        })
+    }
+
+    /// decode to a string, invalid bytes and unknown ids become U+FFFD
+    pub fn decode_lossy(&self, ids: &[u32]) -> String {
+        let mut bytes = Vec::new();
+        for &id in ids {
+            bytes.extend_from_slice(self.token_bytes(id).unwrap_or("\u{FFFD}".as_bytes()));
+        }
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    /// helper for the decoders: the bytes and where each token's bytes end
+    fn decode_with_ends(&self, ids: &[u32]) -> Result<(Vec<u8>, Vec<usize>)> {
+        let mut bytes = Vec::new();
+        let mut ends = Vec::with_capacity(ids.len());
+        for (position, &id) in ids.iter().enumerate() {
+            let token = self.token_bytes(id).ok_or(TokenizerError::UnknownToken { id, position })?;
+            bytes.extend_from_slice(token);
+            ends.push(bytes.len());
+        }
+        Ok((bytes, ends))
+    }
+}
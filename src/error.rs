@@ -0,0 +1,82 @@
+// the one error type of the crate... every public function that can fail returns it
+// so an application embedding the tokenizer gets an error to handle instead of a panic
+
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum TokenizerError {
+    /// reading or writing a file failed
+    Io(io::Error),
+    /// a split pattern that does not compile
+    InvalidPattern(String),
+    /// ids[position] is not a raw byte, a merge or a special token
+    UnknownToken { id: u32, position: usize },
+    /// the decoded bytes are not UTF-8 starting at byte 'offset', inside token ids[position]
+    InvalidUtf8 { id: u32, position: usize, offset: usize },
+    /// an allowed special token was never registered
+    UnknownSpecialToken(String),
+    /// special token text was found while encoding w/ AllowedSpecial::NoneRaise
+    DisallowedSpecialToken(String),
+    /// the id is taken by a raw byte, a merge or another special token
+    SpecialTokenIdInUse(u32),
+    /// empty or containing whitespace, which the model file cannot hold
+    InvalidSpecialToken(String),
+    /// a model or rank file that cannot be parsed
+    CorruptModel(String),
+    /// a training corpus w/ a line that is not what its format says (e.g. bad JSONL)
+    InvalidCorpus(String),
+    /// the vocabulary cannot be written in the minbpe format (e.g. it has a byte shuffle)
+    Unsupported(String),
+}
+
+impl fmt::Display for TokenizerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenizerError::Io(err) => write!(f, "{}", err),
+            TokenizerError::InvalidPattern(err) => write!(f, "invalid split pattern: {}", err),
+            TokenizerError::UnknownToken { id, position } =>
+                write!(f, "unknown token id {} at position {}", id, position),
+            TokenizerError::InvalidUtf8 { id, position, offset } =>
+                write!(f, "invalid UTF-8 at byte {} (token id {} at position {})", offset, id, position),
+            TokenizerError::UnknownSpecialToken(special) => write!(f, "unknown special token {:?}", special),
+            TokenizerError::DisallowedSpecialToken(special) => write!(f, "disallowed special token {:?} found in text", special),
+            TokenizerError::SpecialTokenIdInUse(idx) => write!(f, "special token id {} is already in use", idx),
+            TokenizerError::InvalidSpecialToken(special) => write!(f, "invalid special token {:?}", special),
+            TokenizerError::CorruptModel(message) => write!(f, "corrupt model file: {}", message),
+            TokenizerError::InvalidCorpus(message) => write!(f, "invalid corpus: {}", message),
+            TokenizerError::Unsupported(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for TokenizerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TokenizerError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TokenizerError {
+    fn from(err: io::Error) -> TokenizerError {
+        TokenizerError::Io(err)
+    }
+}
+
+impl From<regex::Error> for TokenizerError {
+    fn from(err: regex::Error) -> TokenizerError {
+        TokenizerError::InvalidPattern(err.to_string())
+    }
+}
+
+#[cfg(feature = "fancy-regex")]
+impl From<fancy_regex::Error> for TokenizerError {
+    fn from(err: fancy_regex::Error) -> TokenizerError {
+        TokenizerError::InvalidPattern(err.to_string())
+    }
+}
+
+/// shorthand for results of this crate
+pub type Result<T> = std::result::Result<T, TokenizerError>;
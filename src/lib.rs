@@ -0,0 +1,49 @@
+// byte-level BPE tokenizer following minbpe (https://github.com/karpathy/minbpe)
+// the Tokenizer trait below is the interface other crates should depend on
+
+pub mod training;
+pub mod normalizer;
+pub mod corpus;
+pub mod pretokenizer;
+pub mod scanner;
+mod unicode_tables;
+pub mod bpe;
+pub mod cache;
+pub mod merge_heap;
+pub mod persistence;
+pub mod tiktoken;
+pub mod special;
+pub mod observer;
+pub mod stream;
+pub mod decode;
+pub mod offsets;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod error;
+
+pub use bpe::BpeTokenizer;
+pub use cache::CacheStats;
+pub use corpus::Corpus;
+pub use error::TokenizerError;
+pub use normalizer::{Normalization, Normalizer};
+pub use offsets::TokenSpan;
+pub use special::AllowedSpecial;
+pub use stream::StreamDecoder;
+pub use training::{TrainerConfig, Vocabulary};
+
+
+/// common interface shared by the tokenizers in this crate
+pub trait Tokenizer {
+    /// learn merges from the text until the vocabulary holds 'vocab_size' tokens
+    fn train(&mut self, text: &str, vocab_size: u32, verbose: bool) -> Result<(), TokenizerError>;
+
+    /// convert text into a sequence of token ids
+    fn encode(&self, text: &str) -> Result<Vec<u32>, TokenizerError>;
+
+    /// convert a sequence of token ids back into text, bytes that are
+    /// not UTF-8 and unknown ids become U+FFFD
+    fn decode(&self, ids: &[u32]) -> String;
+
+    /// one past the highest token id the tokenizer can produce (see Vocabulary::len())
+    fn vocab_size(&self) -> usize;
+}
@@ -4,157 +4,295 @@
 //       for an alternative approach (https://github.com/google/sentencepiece)
 
 
+use std::collections::HashMap;
+use std::error::Error;
 use std::fs;
 use std::fs::File;
-use std::io::Write;
+use std::io::{self, BufWriter, Read, Write};
 use std::time::Instant;
-use std::collections::HashMap;
-use std::collections::HashSet;
 
-pub mod training;
+use tokenizer::observer::{FileTraceObserver, NoObserver, TrainingObserver};
+use tokenizer::pretokenizer;
+use tokenizer::tiktoken;
+use tokenizer::training::Verbosity;
+use tokenizer::{AllowedSpecial, BpeTokenizer, Corpus, Normalizer, Tokenizer, TrainerConfig, Vocabulary};
 
+const USAGE: &str = "\
+usage: tokenizer <command> [options]
 
-fn main() -> Result<(), std::io::Error> {
-    // fetch data and run training to get maps
-    let data_file_path = "data/train_text.txt";
-    let data = fs::read_to_string(data_file_path)?;
-    let training_set: &str = &data;
+commands:
+  train    --input <file> --out <prefix> [--vocab-size <n>] [--trace-dir <dir>] [--verbose]
+           [--split gpt2|gpt4|o200k|llama3|none] [--normalize <steps>]
+           learn merges from the text file, writes <prefix>.model and <prefix>.vocab...
+           steps are comma separated: nfc, nfkc, strip-control, newlines
+           --dir <dir>, --lines <file> or --jsonl <file> [--text-field <name>] instead of
+           --input stream a corpus: every file below dir, one document per line or the
+           \"text\" (or <name>) string of each JSON line
+  encode   --model <file> [--input <file>] [--allowed-special all|none|none_raise]
+           text (file or stdin) to token ids on stdout, separated by spaces
+  decode   --model <file> [--input <file>] [--lossy]
+           token ids (file or stdin) back to text on stdout, failing on invalid
+           UTF-8 unless --lossy replaces it w/ U+FFFD
+  inspect  --model <file>
+           print every token of the model
+  demo     train on data/train_text.txt and encode data/encode_text.txt,
+           writing the traces in data/output for check_output.sh
 
-    // train tokenizer 
-    println!("");
-    println!("START TRAINING");
-    println!("##############################");
-    let start = Instant::now();
-    let tokenizer = crate::training::execute(training_set, false);
-    let duration = start.elapsed();
-    println!("Training took {:.2} seconds", duration.as_secs_f64());
-    println!("##############################");
-    println!("END TRAINING");
+models are minbpe .model files or tiktoken rank files (.tiktoken, loaded as cl100k_base)";
+
+/// the options each command takes, anything else is a usage error
+const TRAIN_OPTIONS: [&str; 11] = [
+    "input", "out", "vocab-size", "trace-dir", "verbose", "split", "normalize", "dir", "lines", "jsonl", "text-field",
+];
+const ENCODE_OPTIONS: [&str; 3] = ["model", "input", "allowed-special"];
+const DECODE_OPTIONS: [&str; 3] = ["model", "input", "lossy"];
+const INSPECT_OPTIONS: [&str; 1] = ["model"];
+
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("train") => parse_options(&args[1..], &TRAIN_OPTIONS).and_then(|options| train(&options)),
+        Some("encode") => parse_options(&args[1..], &ENCODE_OPTIONS).and_then(|options| encode(&options)),
+        Some("decode") => parse_options(&args[1..], &DECODE_OPTIONS).and_then(|options| decode(&options)),
+        Some("inspect") => parse_options(&args[1..], &INSPECT_OPTIONS).and_then(|options| inspect(&options)),
+        Some("demo") => demo(),
+        Some("help") | Some("--help") | Some("-h") => {
+            println!("{}", USAGE);
+            Ok(())
+        },
+        _ => Err(usage_error("missing or unknown command")),
+    };
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+
+/// learn a vocabulary from a text file and save it
+fn train(options: &HashMap<String, String>) -> Result<(), Box<dyn Error>> {
+    let corpus = match (options.get("dir"), options.get("lines"), options.get("jsonl")) {
+        (Some(dir), None, None) => Some(Corpus::Directory(dir.into())),
+        (None, Some(path), None) => Some(Corpus::Lines(path.into())),
+        (None, None, Some(path)) => Some(Corpus::Jsonl {
+            path: path.into(),
+            text_field: options.get("text-field").cloned().unwrap_or_else(|| "text".to_string()),
+        }),
+        (None, None, None) => None,
+        _ => return Err(usage_error("only one of --dir, --lines and --jsonl can be given")),
+    };
+    if corpus.is_some() && options.contains_key("input") {
+        return Err(usage_error("--input can't be combined w/ --dir, --lines or --jsonl"));
+    }
+    let out = required(options, "out")?;
+    let config = TrainerConfig {
+        vocab_size: match options.get("vocab-size") {
+            Some(size) => size.parse().map_err(|_| usage_error("--vocab-size must be a number"))?,
+            None => TrainerConfig::default().vocab_size,
+        },
+        verbosity: if options.contains_key("verbose") { Verbosity::Merges } else { Verbosity::Summary },
+        normalizer: match options.get("normalize") {
+            Some(steps) => Normalizer::parse(steps).map_err(|err| usage_error(&err.to_string()))?,
+            None => Normalizer::default(),
+        },
+        pre_tokenizer: match options.get("split") {
+            Some(name) => pretokenizer::preset(name)
+                .ok_or_else(|| usage_error(&format!("unknown --split preset {:?}", name)))?,
+            None => pretokenizer::gpt4(),
+        },
+        ..TrainerConfig::default()
+    };
+
+    let mut tokenizer = BpeTokenizer::new();
+    let mut trace = options.get("trace-dir").map(FileTraceObserver::create).transpose()?;
+    let observer: &mut dyn TrainingObserver = match &mut trace {
+        Some(trace) => trace,
+        None => &mut NoObserver,
+    };
+    match &corpus {
+        Some(corpus) => tokenizer.train_from_corpus(corpus, &config, observer)?,
+        None => tokenizer.train_with_config(&fs::read_to_string(required(options, "input")?)?, &config, observer)?,
+    }
+    if let Some(trace) = trace {
+        trace.finish()?;
+    }
+    tokenizer.vocab.save(out)?;
+    Ok(())
+}
+
+
+/// text to ids, one line of space separated ids
+fn encode(options: &HashMap<String, String>) -> Result<(), Box<dyn Error>> {
+    let tokenizer = load_model(options)?;
+    let text = read_input(options)?;
+    let allowed = match options.get("allowed-special").map(String::as_str) {
+        Some("all") => AllowedSpecial::All,
+        Some("none") => AllowedSpecial::None,
+        Some("none_raise") | None => AllowedSpecial::NoneRaise,
+        Some(other) => return Err(usage_error(&format!("unknown --allowed-special {:?}", other))),
+    };
+    let ids = tokenizer.encode_special(&text, &allowed)?;
+
+    let mut stdout = BufWriter::new(io::stdout().lock());
+    let line: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+    writeln!(stdout, "{}", line.join(" "))?;
+    stdout.flush()?;
+    Ok(())
+}
+
+
+/// whitespace separated ids back to text
+fn decode(options: &HashMap<String, String>) -> Result<(), Box<dyn Error>> {
+    let tokenizer = load_model(options)?;
+    let ids = read_input(options)?
+        .split_whitespace()
+        .map(|id| id.parse::<u32>().map_err(|_| usage_error(&format!("not a token id: {:?}", id))))
+        .collect::<Result<Vec<u32>, _>>()?;
+
+    let text = if options.contains_key("lossy") {
+        tokenizer.vocab.decode_lossy(&ids)
+    } else {
+        tokenizer.vocab.decode_strict(&ids)?
+    };
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(text.as_bytes())?;
+    stdout.flush()?;
+    Ok(())
+}
+
+
+/// print the vocabulary the way the .vocab file shows it
+fn inspect(options: &HashMap<String, String>) -> Result<(), Box<dyn Error>> {
+    let tokenizer = load_model(options)?;
+    let mut stdout = BufWriter::new(io::stdout().lock());
+    tokenizer.vocab.write_vocab(&mut stdout)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+
+/// the original fixed pipeline over the files in data/
+fn demo() -> Result<(), Box<dyn Error>> {
+    // reuse a previously saved model so training only happens once
+    let model_prefix = "data/output/tokenizer";
+    let model_file = format!("{}.model", model_prefix);
+    let tokenizer = if fs::metadata(&model_file).is_ok() {
+        println!("loading saved model from {}", model_file);
+        BpeTokenizer::from_vocabulary(Vocabulary::load(&model_file)?)
+    } else {
+        // fetch data and run training to get maps
+        let data_file_path = "data/train_text.txt";
+        let data = fs::read_to_string(data_file_path)?;
+        let training_set: &str = &data;
+
+        // train tokenizer 
+        println!();
+        println!("START TRAINING");
+        println!("##############################");
+        let start = Instant::now();
+        // keep the chunk and merge traces for data/output/check_output.sh
+        let mut trace = FileTraceObserver::create("data/output")?;
+        let mut tokenizer = BpeTokenizer::new();
+        tokenizer.train_with_config(training_set, &TrainerConfig::default(), &mut trace)?;
+        trace.finish()?;
+        let duration = start.elapsed();
+        println!("Training took {:.2} seconds", duration.as_secs_f64());
+        println!("##############################");
+        println!("END TRAINING");
+
+        tokenizer.vocab.save(model_prefix)?;
+        tokenizer
+    };
 
     // encode example
     let example_file_path = "data/encode_text.txt";
     let example = fs::read_to_string(example_file_path)?;
     let text_example: &str = &example;
-    println!("");
+    println!();
     println!("START ENCODING");
     println!("##############################");
-    let text_encoded = encode(&tokenizer, text_example, false);
+    let text_encoded = tokenizer.encode(text_example)?;
+    let start_len = text_example.len();
+    let end_len = text_encoded.len();
+    println!("starting length: {},\nending length: {}", start_len, end_len);
+    println!("encoding compression ratio: {}", start_len as f32 / end_len as f32);
     
     // write output tokens to file
     let mut file = File::create("data/output/encode_output.txt")?;
     for token in &text_encoded {
-        writeln!(file, "{}", tokenizer.stringify_word(&[*token]))?;
+        writeln!(file, "{}", tokenizer.vocab.stringify_word(&[*token]))?;
     }
     println!("##############################");
     println!("END ENCODING");
 
     // decode what was encoded for checking
-    let text_decoded = decode(&tokenizer, text_encoded);
-    
-    // checks
-    /*println!("");
-    assert_eq!(text_example.as_bytes().to_vec(), text_decoded.as_bytes().to_vec());
-    println!("if you can see this then they were byte-wise equal!");
-    println!("");*/ 
+    let text_decoded = tokenizer.decode(&text_encoded);
+    if text_decoded.as_bytes() != text_example.as_bytes() {
+        println!("decoded text does not match the original!");
+    }
     
     Ok(())
 }
 
 
-fn encode(vocab: &crate::training::Vocabulary, text: &str, verbose: bool) -> Vec<u32> {
-    let start_len: usize = text.len();
-    let end_len: usize;
-    // split the text into chunks and translate to Vec<u32> to hold the extended bytes
-    let split_text: Vec<String> = crate::training::split(text);
-    let mut split_bytes_ext: Vec<Vec<u32>> = 
-        split_text
-            .iter()
-            .map(|s| s.as_bytes().iter().map(|&b| b as u32).collect())
-            .collect();
-    let mut pairs_count: HashMap<(u32, u32), u32> = HashMap::new();
-    let mut pairs_set: HashSet<(u32, u32)> = HashSet::new();
-    let mut check_pairs = true;
-    // check map in the order tokens were created
-    for (pair, ext_byte) in &vocab.vocab_vec {
-    
-        // if output has been updated, get pairs across all chunks
-        if check_pairs == true {
-            for chunk in &split_bytes_ext {
-                let chunk_pairs = crate::training::pair_counts(chunk);
-                for (&key, &count) in chunk_pairs.iter() {
-                    *pairs_count.entry(key).or_insert(0) += count;
-                }
-            }
-            pairs_set = 
-                pairs_count.iter()
-                .filter(|&(_, &count)| count >= 2)
-                .map(|(&key, _)| key)
-                .collect();
-        }
-        
-        // if the pair in the map is a pair in one of the chunks
-        if pairs_set.contains(&pair) {
-            // iterate through each chunk
-            for chunk in &mut split_bytes_ext {
-                let (byte1, byte2) = pair;
-                let mut i = 0;
-                // look for the pair in the chunk
-                while i < chunk.len() - 1 {
-                    if chunk[i] == *byte1 && chunk[i + 1] == *byte2 {
-                        // print replacements
-                        if verbose == true {
-                            let string_byte1 = vocab.stringify_word(&[*byte1]);
-                            let string_byte2 = vocab.stringify_word(&[*byte2]);
-                            let string_view = format!("{}{}", &string_byte1, &string_byte2);
-                            println!("replacing {:?}, {:?} with {:?}", 
-                                string_byte1,
-                                string_byte2,
-                                string_view);
-                        }
-                        // replace the pair with the new word
-                        chunk[i] = *ext_byte;
-                        chunk.remove(i + 1);
-                        check_pairs = true;
-                    } else {
-                        i += 1;
-                    }
-                }
-            }
-        } else {
-            // no need to find pairs again
-            // if no replacements were made
-            check_pairs = false;
-        }
-    }    
-    
-    // de-chunk to prepare for output
-    let mut encoded_text: Vec<u32> = vec!();
-    for chunk in split_bytes_ext {
-        let mut i = 0;
-        while i < chunk.len() {
-            encoded_text.push(chunk[i]);
-            i += 1;
+/// "--name value" pairs into a map... flags w/o a value (e.g. --verbose) map to ""
+/// and a name not in 'allowed' is an error
+fn parse_options(args: &[String], allowed: &[&str]) -> Result<HashMap<String, String>, Box<dyn Error>> {
+    let mut options = HashMap::new();
+    let mut args = args.iter().peekable();
+    while let Some(arg) = args.next() {
+        let name = arg
+            .strip_prefix("--")
+            .ok_or_else(|| usage_error(&format!("unexpected argument {:?}", arg)))?;
+        if !allowed.contains(&name) {
+            return Err(usage_error(&format!("unknown option --{}", name)));
         }
+        let value = match args.peek() {
+            Some(value) if !value.starts_with("--") => args.next().unwrap().clone(),
+            _ => String::new(),
+        };
+        options.insert(name.to_string(), value);
     }
-    end_len = encoded_text.len();
-    println!("starting length: {},\nending length: {}", start_len, end_len);
-    println!("encoding compression ratio: {}", start_len as f32 / end_len as f32);
-    encoded_text
+    Ok(options)
 }
 
 
-fn decode(vocab: &crate::training::Vocabulary, tokens_vector: Vec<u32>) -> String {
-    let mut tokens = tokens_vector;
-    let mut i = 0;
-    while i < tokens.len() {
-        if let Some(&(byte1, byte2)) = vocab.vocab_hash.get(&tokens[i]) {
-            tokens[i] = byte1;                          // replace current token with its first component
-            tokens.insert(i+1, byte2);   // insert second component next to first
-            continue;                                   // allows inserts to be checked
-        }
-        i += 1;  // move to the next token
+/// helper for the commands: an option that has to be given a value
+fn required<'a>(options: &'a HashMap<String, String>, name: &str) -> Result<&'a str, Box<dyn Error>> {
+    match options.get(name) {
+        Some(value) if !value.is_empty() => Ok(value),
+        _ => Err(usage_error(&format!("--{} is required", name))),
+    }
+}
+
+
+/// helper for the commands: a minbpe .model or a tiktoken rank file
+fn load_model(options: &HashMap<String, String>) -> Result<BpeTokenizer, Box<dyn Error>> {
+    let path = required(options, "model")?;
+    let vocab = if path.ends_with(".tiktoken") {
+        tiktoken::cl100k_base(path)?
+    } else {
+        Vocabulary::load(path)?
+    };
+    Ok(BpeTokenizer::from_vocabulary(vocab))
+}
+
+
+/// helper for the commands: the --input file, or stdin when there is none
+fn read_input(options: &HashMap<String, String>) -> Result<String, Box<dyn Error>> {
+    match options.get("input") {
+        Some(path) => Ok(fs::read_to_string(path)?),
+        None => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            Ok(input)
+        },
     }
-    // tokens back to bytes and then string
-    let decoded_bytes: Vec<u8> = tokens.into_iter().map(|token| token as u8).collect();
-    String::from_utf8(decoded_bytes).unwrap_or_else(|_| String::from("Decoding Error"))
-}
\ No newline at end of file
+}
+
+
+/// helper for the commands: bad arguments, w/ the usage appended
+fn usage_error(message: &str) -> Box<dyn Error> {
+    format!("{}\n\n{}", message, USAGE).into()
+}
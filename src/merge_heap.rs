@@ -0,0 +1,75 @@
+// encoding a chunk in O(n log n)... encode_chunk() looks for the lowest ranked pair by
+// scanning the whole chunk after every merge, which is fine for words but quadratic on a
+// long chunk (a file w/o a pre-tokenizer, a line of base64, a run of one character)
+//
+// here the ids are a linked list and every adjacent pair w/ a rank sits in a min-heap of
+// (rank, position): popping gives the leftmost occurrence of the lowest ranked pair, merging
+// it only touches its two neighbours and pushes at most two new pairs. entries whose pair
+// has changed since they were pushed are skipped when they come up
+//
+// the result is the same as encode_chunk()'s: a pair made by a merge contains the new token
+// so its rank is higher than the one being merged, every occurrence of the lowest ranked pair
+// is therefore merged (left to right, like training::merge()) before anything it creates
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::bpe::BpeTokenizer;
+
+/// chunks longer than this many bytes are encoded w/ encode_chunk_linear()... shorter
+/// ones are cheaper to rescan than to keep a heap for
+pub const LONG_CHUNK_LEN: usize = 64;
+
+/// marks the end of the list
+const NONE: usize = usize::MAX;
+
+impl BpeTokenizer {
+
+    /// the ids of encode_chunk(), in O(n log n) for a chunk of n bytes
+    pub fn encode_chunk_linear(&self, chunk: &[u8]) -> Vec<u32> {
+        let vocab = &self.vocab;
+        let mut ids: Vec<u32> = chunk.iter().map(|&b| vocab.byte_token(b)).collect();
+        let rank = |left: u32, right: u32| vocab.vocab_ranks.get(&(left, right)).copied();
+
+        // prev/next link the ids still in the list, merged away ones are 'removed'
+        let len = ids.len();
+        let mut prev: Vec<usize> = (0..len).map(|i| i.checked_sub(1).unwrap_or(NONE)).collect();
+        let mut next: Vec<usize> = (1..=len).map(|i| if i < len { i } else { NONE }).collect();
+        let mut removed = vec![false; len];
+        let mut heap: BinaryHeap<Reverse<(u32, usize)>> = (0..len.saturating_sub(1))
+            .filter_map(|i| rank(ids[i], ids[i + 1]).map(|new_word| Reverse((new_word, i))))
+            .collect();
+
+        while let Some(Reverse((new_word, left))) = heap.pop() {
+            // skip stale entries: the left id was merged away or its pair has changed
+            let right = next[left];
+            if removed[left] || right == NONE || rank(ids[left], ids[right]) != Some(new_word) {
+                continue;
+            }
+            // merge right into left
+            ids[left] = new_word;
+            removed[right] = true;
+            next[left] = next[right];
+            if next[right] != NONE {
+                prev[next[right]] = left;
+            }
+            // the pairs w/ the new token on either side
+            if prev[left] != NONE {
+                if let Some(rank) = rank(ids[prev[left]], new_word) {
+                    heap.push(Reverse((rank, prev[left])));
+                }
+            }
+            if next[left] != NONE {
+                if let Some(rank) = rank(new_word, ids[next[left]]) {
+                    heap.push(Reverse((rank, left)));
+                }
+            }
+        }
+
+        // the list is still in position order
+        ids.into_iter()
+            .zip(removed)
+            .filter_map(|(id, removed)| (!removed).then_some(id))
+            .collect()
+    }
+}
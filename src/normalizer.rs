@@ -0,0 +1,122 @@
+// optional clean up of the text before it is split, so the same word typed or copied
+// in different forms gives the same chunks... it runs in training and in encoding and
+// is saved w/ the model, a model trained on normalized text only ever sees normalized text
+//
+// normalization is not undone by decoding: decode(encode(text)) is the normalized text
+
+use std::borrow::Cow;
+use std::fmt;
+use unicode_normalization::{is_nfc_quick, is_nfkc_quick, IsNormalized, UnicodeNormalization};
+
+use crate::error::{Result, TokenizerError};
+
+/// one step of a Normalizer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Normalization {
+    /// canonical composition (e.g. "e" + U+0301 -> "é")
+    Nfc,
+    /// compatibility composition, also folds ligatures, full width forms etc. (e.g. "ﬁ" -> "fi")
+    Nfkc,
+    /// drops control characters other than '\t', '\n' and '\r'
+    StripControl,
+    /// "\r\n" -> "\n"
+    Newlines,
+}
+
+impl Normalization {
+
+    /// the name used in model files and on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Normalization::Nfc => "nfc",
+            Normalization::Nfkc => "nfkc",
+            Normalization::StripControl => "strip-control",
+            Normalization::Newlines => "newlines",
+        }
+    }
+
+    /// inverse of name()
+    pub fn from_name(name: &str) -> Option<Normalization> {
+        match name {
+            "nfc" => Some(Normalization::Nfc),
+            "nfkc" => Some(Normalization::Nfkc),
+            "strip-control" => Some(Normalization::StripControl),
+            "newlines" => Some(Normalization::Newlines),
+            _ => None,
+        }
+    }
+
+    /// helper for Normalizer::normalize(): the step on its own, borrowing when nothing changes
+    fn apply<'t>(self, text: &'t str) -> Cow<'t, str> {
+        match self {
+            Normalization::Nfc if is_nfc_quick(text.chars()) == IsNormalized::Yes => Cow::Borrowed(text),
+            Normalization::Nfc => Cow::Owned(text.nfc().collect()),
+            Normalization::Nfkc if is_nfkc_quick(text.chars()) == IsNormalized::Yes => Cow::Borrowed(text),
+            Normalization::Nfkc => Cow::Owned(text.nfkc().collect()),
+            Normalization::StripControl if !text.chars().any(is_stripped_control) => Cow::Borrowed(text),
+            Normalization::StripControl => Cow::Owned(text.chars().filter(|&c| !is_stripped_control(c)).collect()),
+            Normalization::Newlines if !text.contains("\r\n") => Cow::Borrowed(text),
+            Normalization::Newlines => Cow::Owned(text.replace("\r\n", "\n")),
+        }
+    }
+}
+
+
+/// the steps run over the text in order before it is split (none by default)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Normalizer {
+    steps: Vec<Normalization>,
+}
+
+impl Normalizer {
+
+    /// runs 'steps' in the order given
+    pub fn new(steps: Vec<Normalization>) -> Normalizer {
+        Normalizer { steps }
+    }
+
+    pub fn steps(&self) -> &[Normalization] {
+        &self.steps
+    }
+
+    /// true when the text is used as is
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// the text w/ every step applied, borrowed when it was already normalized
+    pub fn normalize<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        let mut normalized = Cow::Borrowed(text);
+        for step in &self.steps {
+            if let Cow::Owned(changed) = step.apply(&normalized) {
+                normalized = Cow::Owned(changed);
+            }
+        }
+        normalized
+    }
+
+    /// parses the comma separated step names of Display (e.g. "nfc,newlines"), "" is no steps
+    pub fn parse(spec: &str) -> Result<Normalizer> {
+        let steps = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| Normalization::from_name(name)
+                .ok_or_else(|| TokenizerError::Unsupported(format!("unknown normalization {:?}", name))))
+            .collect::<Result<Vec<Normalization>>>()?;
+        Ok(Normalizer { steps })
+    }
+}
+
+impl fmt::Display for Normalizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = self.steps.iter().map(|step| step.name()).collect();
+        write!(f, "{}", names.join(","))
+    }
+}
+
+
+/// helper for StripControl: the control characters that carry no layout
+fn is_stripped_control(c: char) -> bool {
+    c.is_control() && !matches!(c, '\t' | '\n' | '\r')
+}
@@ -0,0 +1,89 @@
+// hooks into a training run... the trainer reports what it does and observers decide
+// what to keep (e.g. the trace files that data/output/check_output.sh compares)
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::error::Result;
+use crate::training::Vocabulary;
+
+/// callbacks made by the trainer, all of them do nothing by default
+pub trait TrainingObserver {
+    /// one of the chunks merges are learned from, in order (every occurrence for
+    /// train_tokenizer(), each distinct chunk once for train_from_chunk_counts())
+    fn chunk_produced(&mut self, _chunk: &str) {}
+
+    /// 'pair' seen 'count' times was merged into 'new_word', which 'vocab' already holds
+    fn merge_performed(&mut self, _vocab: &Vocabulary, _pair: (u32, u32), _new_word: u32, _count: u64) {}
+
+    /// training is done and 'vocab' is final
+    fn training_finished(&mut self, _vocab: &Vocabulary) {}
+}
+
+/// observes nothing
+pub struct NoObserver;
+
+impl TrainingObserver for NoObserver {}
+
+
+/// writes the chunk and merge traces the original trainer always wrote to data/output:
+/// chunk_output.txt (one chunk per line) and train_output.txt (one merge per line)
+pub struct FileTraceObserver {
+    chunk_file: BufWriter<File>,
+    merge_file: BufWriter<File>,
+    error: Option<io::Error>,
+}
+
+impl FileTraceObserver {
+
+    /// creates (or truncates) the two trace files in 'dir'
+    pub fn create<P: AsRef<Path>>(dir: P) -> Result<FileTraceObserver> {
+        let dir = dir.as_ref();
+        Ok(FileTraceObserver {
+            chunk_file: BufWriter::new(File::create(dir.join("chunk_output.txt"))?),
+            merge_file: BufWriter::new(File::create(dir.join("train_output.txt"))?),
+            error: None,
+        })
+    }
+
+    /// flushes the files and reports the first write error, if any
+    pub fn finish(mut self) -> Result<()> {
+        if let Some(err) = self.error.take() {
+            return Err(err.into());
+        }
+        self.chunk_file.flush()?;
+        self.merge_file.flush()?;
+        Ok(())
+    }
+
+    /// helper for the callbacks: keep the first error, skip writing after it
+    fn record(&mut self, result: io::Result<()>) {
+        if let (None, Err(err)) = (&self.error, result) {
+            self.error = Some(err);
+        }
+    }
+}
+
+impl TrainingObserver for FileTraceObserver {
+
+    fn chunk_produced(&mut self, chunk: &str) {
+        if self.error.is_some() {
+            return;
+        }
+        let result = writeln!(self.chunk_file, "{}", chunk);
+        self.record(result);
+    }
+
+    fn merge_performed(&mut self, vocab: &Vocabulary, (byte1, byte2): (u32, u32), new_word: u32, _count: u64) {
+        if self.error.is_some() {
+            return;
+        }
+        let result = writeln!(self.merge_file, "[{}][{}] -> [{}] {}", 
+            vocab.stringify_word(&[byte1]), 
+            vocab.stringify_word(&[byte2]), 
+            vocab.stringify_word(&[byte1, byte2]), 
+            new_word);
+        self.record(result);
+    }
+}
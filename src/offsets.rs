@@ -0,0 +1,77 @@
+// where each token came from in the text... the chunks already know their position, and
+// inside a chunk the tokens follow each other byte for byte, so every span is just the
+// running sum of the token lengths
+//
+// a token can start or end in the middle of a multi-byte character (byte-level BPE merges
+// whatever bytes are common), so the char range of a token covers every character it has
+// a byte of... two tokens sharing a character both include it
+
+use std::ops::Range;
+
+use crate::bpe::BpeTokenizer;
+use crate::error::{Result, TokenizerError};
+
+/// a token id and the part of the text it was encoded from
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TokenSpan {
+    pub id: u32,
+    /// byte offsets into the text, text.as_bytes()[bytes] are the token's bytes
+    pub bytes: Range<usize>,
+    /// char offsets into the text (text.chars()), see the top of this file
+    pub chars: Range<usize>,
+}
+
+impl BpeTokenizer {
+
+    /// encode() w/ the span of every token in 'text'... the spans have to be in the text
+    /// as given, so when the vocabulary's Normalizer changes the text (e.g. NFC composing
+    /// "e" + U+0301) there are no offsets and this is Unsupported. text the normalizer
+    /// leaves as it is, and every text w/o a normalizer, has them
+    pub fn encode_with_offsets(&self, text: &str) -> Result<Vec<TokenSpan>> {
+        if self.vocab.normalizer.normalize(text) != text {
+            return Err(TokenizerError::Unsupported(
+                "offsets into text changed by the normalizer are not supported".to_string()));
+        }
+        let mut spans = Vec::new();
+        let mut cursor = CharCursor { text: text.as_bytes(), byte: 0, chars: 0 };
+        let mut chunks = self.vocab.pre_tokenizer.chunks(text);
+        loop {
+            let mut start = chunks.offset();
+            let Some(chunk) = chunks.next() else {
+                break;
+            };
+            for id in self.encode_chunk(chunk.as_bytes(), false) {
+                // every id of encode_chunk() is a byte or a merge, so never empty
+                let end = start + self.vocab.token_bytes(id).map_or(1, <[u8]>::len);
+                let first_char = cursor.char_at(start);
+                let last_char = cursor.char_at(end - 1);
+                spans.push(TokenSpan { id, bytes: start..end, chars: first_char..last_char + 1 });
+                start = end;
+            }
+        }
+        Ok(spans)
+    }
+}
+
+
+/// helper for encode_with_offsets(): byte offset -> index of the char holding it,
+/// for offsets that never go backwards so the text is only walked once
+struct CharCursor<'t> {
+    text: &'t [u8],
+    byte: usize,    // bytes before this one have been counted
+    chars: usize,   // chars starting before 'byte'
+}
+
+impl CharCursor<'_> {
+
+    fn char_at(&mut self, offset: usize) -> usize {
+        while self.byte <= offset {
+            // UTF-8 continuation bytes are 0b10xxxxxx
+            if self.text[self.byte] & 0xC0 != 0x80 {
+                self.chars += 1;
+            }
+            self.byte += 1;
+        }
+        self.chars - 1
+    }
+}
@@ -0,0 +1,45 @@
+// encoding on rayon's thread pool (the "parallel" feature)... chunks are encoded on their
+// own anyway, so they can go to different threads and be joined back in order, which gives
+// exactly the ids of the serial encode()
+//
+// the split itself stays serial: where a chunk ends depends on where it started, and the
+// scanner is fast enough that the merges are what the threads are needed for
+//
+// training shards its pair counting and merging the same way (see count_pairs() and
+// merge_chunks() in training.rs)
+
+use rayon::prelude::*;
+
+use crate::bpe::BpeTokenizer;
+use crate::error::Result;
+
+/// chunks handed to a thread at once... single chunks are too little work to be worth a task
+const CHUNKS_PER_TASK: usize = 256;
+
+impl BpeTokenizer {
+
+    /// encode() w/ the chunks spread over the threads of the current rayon pool
+    pub fn encode_parallel(&self, text: &str) -> Result<Vec<u32>> {
+        let text = self.vocab.normalizer.normalize(text);
+        let chunks: Vec<&str> = self.vocab.pre_tokenizer.chunks(&text).collect();
+        let blocks: Vec<Vec<u32>> = chunks
+            .par_chunks(CHUNKS_PER_TASK)
+            .map(|block| {
+                let mut ids = Vec::new();
+                for chunk in block {
+                    ids.extend(self.encode_chunk(chunk.as_bytes(), false));
+                }
+                ids
+            })
+            .collect();
+        Ok(blocks.concat())
+    }
+
+    /// encode() of every document, one document per task, in the order given
+    pub fn encode_batch(&self, texts: &[&str]) -> Result<Vec<Vec<u32>>> {
+        texts
+            .par_iter()
+            .map(|text| self.encode_verbose(text, false))
+            .collect()
+    }
+}
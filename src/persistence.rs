@@ -0,0 +1,161 @@
+// save/load in the same format as minbpe's base.py so that a model trained by
+// either implementation can be reloaded by the other
+//
+// prefix.model -> "minbpe v1", the split pattern, the special tokens, then one merge per line
+//                 (a model w/ a Normalizer starts w/ "minbpe v1 normalized" and a line of its
+//                 steps instead, which minbpe refuses rather than encoding the text differently)
+// prefix.vocab -> pretty printed tokens for human inspection only (never read back)
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::OnceLock;
+use regex::Regex;
+
+use crate::error::{Result, TokenizerError};
+use crate::normalizer::Normalizer;
+use crate::pretokenizer;
+use crate::training::Vocabulary;
+
+const MODEL_VERSION: &str = "minbpe v1";
+const NORMALIZED_MODEL_VERSION: &str = "minbpe v1 normalized";
+
+impl Vocabulary {
+
+    /// writes file_prefix.model (for load()) and file_prefix.vocab (for humans)
+    pub fn save(&self, file_prefix: &str) -> Result<()> {
+        // like minbpe's GPT4Tokenizer, the byte permutation has no place in the format
+        if self.byte_shuffle.is_some() {
+            return Err(TokenizerError::Unsupported(
+                "vocabularies w/ a byte shuffle (tiktoken) cannot be saved".to_string()));
+        }
+        // the model file: version, pattern, special tokens then the merges in order
+        let mut model = BufWriter::new(File::create(format!("{}.model", file_prefix))?);
+        if self.normalizer.is_empty() {
+            writeln!(model, "{}", MODEL_VERSION)?;
+        } else {
+            writeln!(model, "{}", NORMALIZED_MODEL_VERSION)?;
+            writeln!(model, "{}", self.normalizer)?;
+        }
+        writeln!(model, "{}", self.pre_tokenizer.pattern())?;
+        writeln!(model, "{}", self.special_tokens.len())?;
+        for (special, idx) in &self.special_tokens {
+            writeln!(model, "{} {}", special, idx)?;
+        }
+        for ((idx1, idx2), _) in &self.vocab_vec {
+            writeln!(model, "{} {}", idx1, idx2)?;
+        }
+        model.flush()?;
+
+        // the vocab file: for the human to look at
+        let mut vocab = BufWriter::new(File::create(format!("{}.vocab", file_prefix))?);
+        self.write_vocab(&mut vocab)?;
+        vocab.flush()?;
+        Ok(())
+    }
+
+    /// every token rendered one per line, merges shown with their children
+    pub fn write_vocab(&self, out: &mut dyn Write) -> Result<()> {
+        for idx in 0..256u32 {
+            writeln!(out, "[{}] {}", render_token(&self.expand_bytes(&[idx])), idx)?;
+        }
+        for &((idx0, idx1), idx) in &self.vocab_vec {
+            writeln!(out, "[{}][{}] -> [{}] {}",
+                render_token(&self.expand_bytes(&[idx0])),
+                render_token(&self.expand_bytes(&[idx1])),
+                render_token(&self.expand_bytes(&[idx])),
+                idx)?;
+        }
+        for (special, idx) in &self.special_tokens {
+            writeln!(out, "[{}] {}", render_token(special.as_bytes()), idx)?;
+        }
+        Ok(())
+    }
+
+    /// inverse of save() but only for the model file
+    pub fn load<P: AsRef<Path>>(model_file: P) -> Result<Vocabulary> {
+        let model_file = model_file.as_ref();
+        if model_file.extension().and_then(|ext| ext.to_str()) != Some("model") {
+            return Err(invalid_data(format!("{} is not a .model file", model_file.display())));
+        }
+        let contents = fs::read_to_string(model_file)?;
+        let mut lines = contents.lines();
+
+        // read the version, and the normalizer for the version that has one
+        let normalizer = match lines.next().unwrap_or("").trim() {
+            MODEL_VERSION => Normalizer::default(),
+            NORMALIZED_MODEL_VERSION => lines.next()
+                .ok_or_else(|| invalid_data("missing normalizer".to_string()))
+                .and_then(|steps| Normalizer::parse(steps).map_err(|err| invalid_data(err.to_string())))?,
+            version => return Err(invalid_data(format!("unsupported model version {:?}", version))),
+        };
+
+        // read the pattern and compile it once for the vocabulary
+        let pattern = lines.next()
+            .ok_or_else(|| invalid_data("missing split pattern".to_string()))?
+            .trim();
+        let pre_tokenizer = pretokenizer::from_pattern(pattern)?;
+
+        // read the special tokens, first the number of them, then each one
+        let num_special: usize = lines.next()
+            .and_then(|line| line.trim().parse().ok())
+            .ok_or_else(|| invalid_data("missing special token count".to_string()))?;
+        let mut special_tokens = Vec::with_capacity(num_special);
+        for _ in 0..num_special {
+            let line = lines.next()
+                .ok_or_else(|| invalid_data("missing special token".to_string()))?;
+            let mut parts = line.split_whitespace();
+            match (parts.next(), parts.next().and_then(|idx| idx.parse::<u32>().ok())) {
+                (Some(special), Some(idx)) => special_tokens.push((special.to_string(), idx)),
+                _ => return Err(invalid_data(format!("bad special token line {:?}", line))),
+            }
+        }
+
+        // read the merges, each one minting the next id after the raw bytes
+        let mut vocab = Vocabulary { normalizer, pre_tokenizer, special_tokens, ..Vocabulary::default() };
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            let new_word = 256 + vocab.vocab_vec.len() as u32;
+            let pair: Option<Vec<u32>> = line.split_whitespace().map(|idx| idx.parse().ok()).collect();
+            let (byte1, byte2) = match pair.as_deref() {
+                Some(&[byte1, byte2]) if byte1 < new_word && byte2 < new_word => (byte1, byte2),
+                _ => return Err(invalid_data(format!("bad merge line {:?}", line))),
+            };
+            vocab.add_merge((byte1, byte2), new_word);
+        }
+
+        // special tokens come after the merges, each w/ its own id
+        let first_free = 256 + vocab.vocab_vec.len() as u32;
+        for (i, (special, idx)) in vocab.special_tokens.iter().enumerate() {
+            if *idx < first_free {
+                return Err(invalid_data(format!("special token {:?} has id {} below the merges", special, idx)));
+            }
+            if vocab.special_tokens[..i].iter().any(|(other, other_idx)| other == special || other_idx == idx) {
+                return Err(invalid_data(format!("duplicate special token {:?} {}", special, idx)));
+            }
+        }
+
+        Ok(vocab)
+    }
+}
+
+
+/// pretty print a token the way minbpe does: decode w/ replacement
+/// characters and escape anything in the Unicode "Other" categories
+pub fn render_token(bytes: &[u8]) -> String {
+    static CONTROL: OnceLock<Regex> = OnceLock::new();
+    let control = CONTROL.get_or_init(|| Regex::new(r"\p{C}").unwrap());
+    let text = String::from_utf8_lossy(bytes);
+    control
+        .replace_all(&text, |caps: &regex::Captures| {
+            let ch = caps[0].chars().next().unwrap();
+            format!("\\u{:04x}", ch as u32)
+        })
+        .into_owned()
+}
+
+
+/// helper for load(): a malformed model file
+fn invalid_data(message: String) -> TokenizerError {
+    TokenizerError::CorruptModel(message)
+}
+
@@ -0,0 +1,437 @@
+// how text is cut into chunks before merges are learned or applied... merges never
+// cross a chunk boundary, so the split pattern is part of the model and saved with it
+//
+// the OpenAI patterns are written for Python's regex module and use features the regex
+// crate does not have: possessive quantifiers ('?+', '++') and the '\s+(?!\S)' lookahead.
+// in these patterns a possessive quantifier never gives back anything a later part could
+// use, so dropping the '+' is exact (see greedy_quantifiers() for what counts as one)...
+// the lookahead is emulated in chunk_end(), see there
+//
+// w/ the "fancy-regex" feature FancyRegexPreTokenizer runs the patterns verbatim like
+// tiktoken does... tests/fancy_regex_test.rs checks both give the same chunks for every
+// preset on a large Unicode corpus, no divergence is known
+
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+use regex::Regex;
+
+use crate::error::{Result, TokenizerError};
+use crate::scanner::Gpt4Scanner;
+
+/// GPT-2 (r50k_base / p50k_base)
+pub const GPT2_SPLIT_PATTERN: &str = r"'(?:[sdmt]|ll|ve|re)| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+(?!\S)|\s+";
+
+/// GPT-4 (cl100k_base)
+pub const GPT4_SPLIT_PATTERN: &str = r"'(?i:[sdmt]|ll|ve|re)|[^\r\n\p{L}\p{N}]?+\p{L}+|\p{N}{1,3}| ?[^\s\p{L}\p{N}]++[\r\n]*|\s*[\r\n]|\s+(?!\S)|\s+";
+
+/// GPT-4o (o200k_base)
+pub const O200K_SPLIT_PATTERN: &str = concat!(
+    r"[^\r\n\p{L}\p{N}]?[\p{Lu}\p{Lt}\p{Lm}\p{Lo}\p{M}]*[\p{Ll}\p{Lm}\p{Lo}\p{M}]+(?i:'s|'t|'re|'ve|'m|'ll|'d)?|",
+    r"[^\r\n\p{L}\p{N}]?[\p{Lu}\p{Lt}\p{Lm}\p{Lo}\p{M}]+[\p{Ll}\p{Lm}\p{Lo}\p{M}]*(?i:'s|'t|'re|'ve|'m|'ll|'d)?|",
+    r"\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n/]*|\s*[\r\n]+|\s+(?!\S)|\s+",
+);
+
+/// Llama 3 (the cl100k pattern w/ case insensitive contractions and newline runs)
+pub const LLAMA3_SPLIT_PATTERN: &str = r"(?i:'s|'t|'re|'ve|'m|'ll|'d)|[^\r\n\p{L}\p{N}]?\p{L}+|\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n]*|\s*[\r\n]+|\s+(?!\S)|\s+";
+
+/// the alternatives ending every preset, the first one cannot run on the regex crate
+const WHITESPACE_LOOKAHEAD: &str = r"|\s+(?!\S)|\s+";
+
+/// what they are rewritten to... the group tells chunk_end() when to apply the lookahead
+const WHITESPACE_GROUP: &str = r"|(\s+)";
+
+/// cuts text into the chunks that are encoded independently
+pub trait PreTokenizer: AsPreTokenizer + fmt::Debug + Send + Sync {
+    /// the pattern recorded in the model file ("" when the text is not split)
+    fn pattern(&self) -> &str;
+
+    /// where the chunk starting at byte 'start' of the text ends (start < text.len())
+    fn chunk_end(&self, text: &str, start: usize) -> usize;
+
+    /// the chunks as owned strings, see chunks() for slices
+    fn split(&self, text: &str) -> Vec<String> {
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        while start < text.len() {
+            let end = self.chunk_end(text, start);
+            chunks.push(text[start..end].to_string());
+            start = end;
+        }
+        chunks
+    }
+
+    /// the chunks of 'text' as slices of it, nothing is allocated
+    fn chunks<'p, 't>(&'p self, text: &'t str) -> Chunks<'p, 't> {
+        Chunks { pre_tokenizer: self.as_pre_tokenizer(), text, pos: 0 }
+    }
+}
+
+/// helper for chunks(): the pre-tokenizer as a trait object, whether it is one already
+/// or a concrete type... implemented for every PreTokenizer
+pub trait AsPreTokenizer {
+    fn as_pre_tokenizer(&self) -> &dyn PreTokenizer;
+}
+
+impl<P: PreTokenizer> AsPreTokenizer for P {
+    fn as_pre_tokenizer(&self) -> &dyn PreTokenizer {
+        self
+    }
+}
+
+/// iterator returned by chunks()
+pub struct Chunks<'p, 't> {
+    pre_tokenizer: &'p dyn PreTokenizer,
+    text: &'t str,
+    pos: usize,
+}
+
+impl<'t> Chunks<'_, 't> {
+
+    /// byte offset in the text of the next chunk
+    pub fn offset(&self) -> usize {
+        self.pos
+    }
+}
+
+impl<'t> Iterator for Chunks<'_, 't> {
+    type Item = &'t str;
+
+    fn next(&mut self) -> Option<&'t str> {
+        if self.pos >= self.text.len() {
+            return None;
+        }
+        let start = self.pos;
+        // always move forward, whatever the pre-tokenizer says
+        self.pos = self.pre_tokenizer.chunk_end(self.text, start).clamp(start + 1, self.text.len());
+        while !self.text.is_char_boundary(self.pos) {
+            self.pos += 1;
+        }
+        Some(&self.text[start..self.pos])
+    }
+}
+
+
+/// splits w/ a regular expression, compiled once when it is created
+#[derive(Debug)]
+pub struct RegexPreTokenizer {
+    pattern: String,
+    regex: Regex,
+    whitespace_lookahead: bool,     // the pattern ended in '\s+(?!\S)|\s+'
+}
+
+impl RegexPreTokenizer {
+
+    /// compiles a Python style split pattern (see the top of this file for what is rewritten)
+    pub fn new(pattern: &str) -> Result<RegexPreTokenizer> {
+        let (translated, whitespace_lookahead) = match pattern.strip_suffix(WHITESPACE_LOOKAHEAD) {
+            Some(rest) => (format!("{}{}", rest, WHITESPACE_GROUP), true),
+            None => (pattern.to_string(), false),
+        };
+        let translated = greedy_quantifiers(&translated)?;
+        Ok(RegexPreTokenizer {
+            pattern: pattern.to_string(),
+            regex: Regex::new(&translated)?,
+            whitespace_lookahead,
+        })
+    }
+
+    /// helper for chunk_end(): where the match of the whitespace lookahead would have ended...
+    /// '\s+(?!\S)' leaves the last space of a run in front of a non-space to the next
+    /// chunk (e.g. "  world" -> " ", " world") and '\s+' takes a single space on its own
+    fn lookahead_end(&self, text: &str, start: usize, end: usize) -> usize {
+        let matched = &text[start..end];
+        let followed_by_text = text[end..].chars().next().is_some_and(|next| !next.is_whitespace());
+        let Some(last) = matched.chars().next_back() else {
+            return end;
+        };
+        if !self.whitespace_lookahead
+            || !followed_by_text
+            || matched.len() == last.len_utf8()
+            || !matched.chars().all(char::is_whitespace)
+        {
+            return end;
+        }
+        // a run of spaces, but maybe taken by an earlier alternative such as '\s*[\r\n]'
+        match self.regex.captures_at(text, start).and_then(|caps| caps.get(1)) {
+            Some(group) if group.start() == start => end - last.len_utf8(),
+            _ => end,
+        }
+    }
+}
+
+impl PreTokenizer for RegexPreTokenizer {
+
+    fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    fn chunk_end(&self, text: &str, start: usize) -> usize {
+        match self.regex.find_at(text, start) {
+            // text between matches is kept as a chunk of its own so no byte is lost
+            Some(mat) if mat.start() > start => mat.start(),
+            Some(mat) if mat.end() > start => self.lookahead_end(text, start, mat.end()),
+            // an empty match or none at all, nothing else can be found
+            _ => text.len(),
+        }
+    }
+}
+
+
+/// splits w/ the pattern exactly as written, on fancy-regex's backtracking engine
+#[cfg(feature = "fancy-regex")]
+#[derive(Debug)]
+pub struct FancyRegexPreTokenizer {
+    pattern: String,
+    regex: fancy_regex::Regex,
+    fallback: Option<RegexPreTokenizer>,    // for text past the backtracking limit
+}
+
+#[cfg(feature = "fancy-regex")]
+impl FancyRegexPreTokenizer {
+
+    /// compiles the pattern as is, no rewriting needed
+    pub fn new(pattern: &str) -> Result<FancyRegexPreTokenizer> {
+        Ok(FancyRegexPreTokenizer {
+            pattern: pattern.to_string(),
+            regex: fancy_regex::Regex::new(pattern)?,
+            fallback: RegexPreTokenizer::new(pattern).ok(),
+        })
+    }
+}
+
+#[cfg(feature = "fancy-regex")]
+impl PreTokenizer for FancyRegexPreTokenizer {
+
+    fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    fn chunk_end(&self, text: &str, start: usize) -> usize {
+        match self.regex.find_from_pos(text, start) {
+            Ok(Some(mat)) if mat.start() > start => mat.start(),
+            Ok(Some(mat)) if mat.end() > start => mat.end(),
+            Ok(_) => text.len(),
+            // backtracking ran too long, use the regex crate translation (or keep the rest whole)
+            Err(_) => match &self.fallback {
+                Some(fallback) => fallback.chunk_end(text, start),
+                None => text.len(),
+            },
+        }
+    }
+}
+
+
+/// no splitting, the whole text is one chunk (minbpe's BasicTokenizer)
+#[derive(Debug)]
+pub struct NoSplit;
+
+impl PreTokenizer for NoSplit {
+
+    fn pattern(&self) -> &str {
+        ""
+    }
+
+    fn chunk_end(&self, text: &str, _start: usize) -> usize {
+        text.len()
+    }
+}
+
+
+/// the GPT-2 preset
+pub fn gpt2() -> Arc<dyn PreTokenizer> {
+    static PRESET: OnceLock<Arc<RegexPreTokenizer>> = OnceLock::new();
+    compiled(&PRESET, GPT2_SPLIT_PATTERN)
+}
+
+/// the GPT-4 (cl100k) preset, the default... runs on the hand-written scanner
+/// (RegexPreTokenizer::new(GPT4_SPLIT_PATTERN) gives the same chunks)
+pub fn gpt4() -> Arc<dyn PreTokenizer> {
+    Arc::new(Gpt4Scanner)
+}
+
+/// the GPT-4o (o200k) preset
+pub fn o200k() -> Arc<dyn PreTokenizer> {
+    static PRESET: OnceLock<Arc<RegexPreTokenizer>> = OnceLock::new();
+    compiled(&PRESET, O200K_SPLIT_PATTERN)
+}
+
+/// the Llama 3 preset
+pub fn llama3() -> Arc<dyn PreTokenizer> {
+    static PRESET: OnceLock<Arc<RegexPreTokenizer>> = OnceLock::new();
+    compiled(&PRESET, LLAMA3_SPLIT_PATTERN)
+}
+
+/// the "none" preset
+pub fn none() -> Arc<dyn PreTokenizer> {
+    Arc::new(NoSplit)
+}
+
+/// a preset by name: "gpt2", "gpt4" (or "cl100k"), "o200k", "llama3" or "none"
+pub fn preset(name: &str) -> Option<Arc<dyn PreTokenizer>> {
+    match name {
+        "gpt2" => Some(gpt2()),
+        "gpt4" | "cl100k" => Some(gpt4()),
+        "o200k" => Some(o200k()),
+        "llama3" => Some(llama3()),
+        "none" => Some(none()),
+        _ => None,
+    }
+}
+
+/// the pre-tokenizer for a pattern read from a model file, sharing the
+/// compiled presets when the pattern is one of them
+pub fn from_pattern(pattern: &str) -> Result<Arc<dyn PreTokenizer>> {
+    Ok(match pattern {
+        "" => none(),
+        GPT2_SPLIT_PATTERN => gpt2(),
+        GPT4_SPLIT_PATTERN => gpt4(),
+        O200K_SPLIT_PATTERN => o200k(),
+        LLAMA3_SPLIT_PATTERN => llama3(),
+        _ => Arc::new(RegexPreTokenizer::new(pattern)?),
+    })
+}
+
+
+/// what came last in the pattern, for greedy_quantifiers()
+#[derive(Clone, Copy, PartialEq)]
+enum Last {
+    Nothing,        // the start, '(' or '|': a '?' or '+' here is not a quantifier
+    Atom,
+    Quantifier,
+    Modified,       // a quantifier made lazy or possessive, nothing else can follow it
+}
+
+/// helper for RegexPreTokenizer::new(): turns the possessive quantifiers ('?+', '*+', '++',
+/// '{n,m}+') into greedy ones... only a '+' following a quantifier of an atom counts, escapes
+/// ('\++') and character classes ('[?+]') are copied as they are. a possessive quantifier
+/// the regex crate has no equivalent for (e.g. lazy then possessive '+?+') is an error
+fn greedy_quantifiers(pattern: &str) -> Result<String> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut translated = String::with_capacity(pattern.len());
+    let mut last = Last::Nothing;
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        let quantifier = match chars[i] {
+            '\\' => {
+                i = escape_end(&chars, i);
+                false
+            },
+            '[' => {
+                i = class_end(&chars, i);
+                false
+            },
+            '(' => {
+                // a group's flags or name are not quantifiers: "(?i:", "(?P<name>"
+                i += 1;
+                if chars.get(i) == Some(&'?') {
+                    while i < chars.len() && chars[i] != ':' && chars[i] != ')' {
+                        i += 1;
+                    }
+                    i = (i + 1).min(chars.len());
+                }
+                translated.extend(&chars[start..i]);
+                last = Last::Nothing;
+                continue;
+            },
+            '|' => {
+                i += 1;
+                translated.push('|');
+                last = Last::Nothing;
+                continue;
+            },
+            '?' | '*' | '+' => {
+                i += 1;
+                true
+            },
+            '{' => match counted_repetition_end(&chars, i) {
+                Some(end) => {
+                    i = end;
+                    true
+                },
+                None => {
+                    i += 1;
+                    false
+                },
+            },
+            _ => {
+                i += 1;
+                false
+            },
+        };
+
+        last = match (quantifier, last) {
+            (false, _) => Last::Atom,
+            (true, Last::Atom) => Last::Quantifier,
+            // possessive: drop the '+' and keep the greedy quantifier before it
+            (true, Last::Quantifier) if chars[start] == '+' => {
+                last = Last::Modified;
+                continue;
+            },
+            (true, Last::Quantifier) if chars[start] == '?' => Last::Modified,
+            (true, Last::Quantifier) | (true, Last::Modified) => {
+                return Err(TokenizerError::InvalidPattern(format!(
+                    "unsupported quantifier {:?} at character {} of {:?}",
+                    chars[start..i].iter().collect::<String>(), start, pattern)));
+            },
+            // nothing to repeat, the regex crate reports it
+            (true, Last::Nothing) => Last::Nothing,
+        };
+        translated.extend(&chars[start..i]);
+    }
+    Ok(translated)
+}
+
+/// helper for greedy_quantifiers(): the end of the escape at 'start', including
+/// the braces of '\p{L}', '\x{..}' and the like
+fn escape_end(chars: &[char], start: usize) -> usize {
+    let mut end = (start + 2).min(chars.len());
+    if matches!(chars.get(start + 1), Some('p' | 'P' | 'x' | 'u' | 'U')) && chars.get(end) == Some(&'{') {
+        while end < chars.len() && chars[end] != '}' {
+            end += 1;
+        }
+        end = (end + 1).min(chars.len());
+    }
+    end
+}
+
+/// helper for greedy_quantifiers(): the end of the character class at 'start', which
+/// may hold escapes, nested classes and a ']' right after the opening '[' or '[^'
+fn class_end(chars: &[char], start: usize) -> usize {
+    let mut i = start + 1;
+    if chars.get(i) == Some(&'^') {
+        i += 1;
+    }
+    if chars.get(i) == Some(&']') {
+        i += 1;
+    }
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i = escape_end(chars, i),
+            '[' => i = class_end(chars, i),
+            ']' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    i   // unterminated, the regex crate reports it
+}
+
+/// helper for greedy_quantifiers(): the end of '{n}', '{n,}' or '{n,m}' at 'start'
+fn counted_repetition_end(chars: &[char], start: usize) -> Option<usize> {
+    let close = start + chars[start..].iter().position(|&c| c == '}')?;
+    let inside = &chars[start + 1..close];
+    let counts = !inside.is_empty()
+        && inside[0].is_ascii_digit()
+        && inside.iter().all(|&c| c.is_ascii_digit() || c == ',')
+        && inside.iter().filter(|&&c| c == ',').count() <= 1;
+    counts.then_some(close + 1)
+}
+
+
+/// helper for the presets: compile the pattern on first use... the presets
+/// are known to compile so a failure here is a bug in this file
+fn compiled(preset: &'static OnceLock<Arc<RegexPreTokenizer>>, pattern: &str) -> Arc<dyn PreTokenizer> {
+    preset
+        .get_or_init(|| Arc::new(RegexPreTokenizer::new(pattern).expect("preset split patterns compile")))
+        .clone()
+}
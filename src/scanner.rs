@@ -0,0 +1,160 @@
+// hand-written scanner for the GPT-4 split pattern... the same chunks as the regex path
+// (see pretokenizer.rs) but w/o a regex engine, the lookahead post-pass or a String per
+// chunk: each alternative of the pattern below is one step of chunk_end()
+//
+//   '(?i:[sdmt]|ll|ve|re)          contractions
+//   [^\r\n\p{L}\p{N}]?+\p{L}+      words, w/ at most one leading space or symbol
+//   \p{N}{1,3}                     numbers, in groups of up to three digits
+//    ?[^\s\p{L}\p{N}]++[\r\n]*     symbols, w/ an optional leading space and trailing newlines
+//   \s*[\r\n]                      whitespace up to the last newline of the run
+//   \s+(?!\S)|\s+                  whitespace, leaving one space for the word that follows
+
+use crate::pretokenizer::{Chunks, PreTokenizer, GPT4_SPLIT_PATTERN};
+use crate::unicode_tables::{LETTER, NUMBER};
+
+/// the GPT-4 pre-tokenizer as a state machine, what pretokenizer::gpt4() returns
+#[derive(Debug)]
+pub struct Gpt4Scanner;
+
+impl PreTokenizer for Gpt4Scanner {
+
+    fn pattern(&self) -> &str {
+        GPT4_SPLIT_PATTERN
+    }
+
+    fn chunk_end(&self, text: &str, start: usize) -> usize {
+        chunk_end(text, start)
+    }
+}
+
+
+/// the chunks of 'text' as slices of it, in order
+pub fn gpt4_chunks(text: &str) -> Chunks<'static, '_> {
+    Gpt4Scanner.chunks(text)
+}
+
+
+/// what the pattern needs to know about a character
+#[derive(Clone, Copy, PartialEq)]
+enum Class {
+    Letter,
+    Number,
+    Newline,    // \r or \n
+    Space,      // any other whitespace
+    Other,
+}
+
+/// helper for the scanner: the class of 'c', ASCII w/o a table lookup
+fn class(c: char) -> Class {
+    match c {
+        'a'..='z' | 'A'..='Z' => Class::Letter,
+        '0'..='9' => Class::Number,
+        '\r' | '\n' => Class::Newline,
+        _ if c.is_whitespace() => Class::Space,
+        _ if c.is_ascii() => Class::Other,
+        _ if in_table(LETTER, c) => Class::Letter,
+        _ if in_table(NUMBER, c) => Class::Number,
+        _ => Class::Other,
+    }
+}
+
+/// helper for class(): binary search of the sorted ranges
+fn in_table(table: &[(char, char)], c: char) -> bool {
+    table
+        .binary_search_by(|&(start, end)| {
+            if end < c {
+                std::cmp::Ordering::Less
+            } else if start > c {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+/// helper for the scanner: the character at byte 'pos' and where the next one starts
+fn char_at(text: &str, pos: usize) -> Option<(char, usize)> {
+    text[pos..].chars().next().map(|c| (c, pos + c.len_utf8()))
+}
+
+/// helper for the scanner: where the run of characters matching 'keep' that starts at 'pos' ends
+fn run_end(text: &str, pos: usize, keep: impl Fn(Class) -> bool) -> usize {
+    text[pos..]
+        .char_indices()
+        .find(|&(_, c)| !keep(class(c)))
+        .map_or(text.len(), |(offset, _)| pos + offset)
+}
+
+/// helper for Gpt4Scanner: where the chunk starting at 'start' ends, trying
+/// the alternatives of the pattern in order (see the top of this file)
+fn chunk_end(text: &str, start: usize) -> usize {
+    let Some((c0, pos1)) = char_at(text, start) else {
+        return start;
+    };
+    let next = char_at(text, pos1);
+
+    // contractions: 's 'd 'm 't (and the long s, which case folds to s) or 'll 've 're
+    if c0 == '\'' {
+        if let Some((c1, pos2)) = next {
+            if matches!(c1, 's' | 'd' | 'm' | 't' | 'S' | 'D' | 'M' | 'T' | 'ſ') {
+                return pos2;
+            }
+            if let Some((c2, pos3)) = char_at(text, pos2) {
+                let pair = (c1.to_ascii_lowercase(), c2.to_ascii_lowercase());
+                if matches!(pair, ('l', 'l') | ('v', 'e') | ('r', 'e')) {
+                    return pos3;
+                }
+            }
+        }
+    }
+
+    let class0 = class(c0);
+    let is_whitespace = |class: Class| matches!(class, Class::Space | Class::Newline);
+    match class0 {
+        // words
+        Class::Letter => return run_end(text, pos1, |class| class == Class::Letter),
+        Class::Space | Class::Other => {
+            if let Some((c1, pos2)) = next {
+                if class(c1) == Class::Letter {
+                    return run_end(text, pos2, |class| class == Class::Letter);
+                }
+            }
+        },
+        // numbers
+        Class::Number => {
+            let mut end = pos1;
+            for _ in 0..2 {
+                match char_at(text, end) {
+                    Some((c, after)) if class(c) == Class::Number => end = after,
+                    _ => break,
+                }
+            }
+            return end;
+        },
+        Class::Newline => {},
+    }
+
+    // symbols
+    let symbols_from = match (c0, next) {
+        (' ', Some((c1, _))) if class(c1) == Class::Other => Some(pos1),
+        _ if class0 == Class::Other => Some(start),
+        _ => None,
+    };
+    if let Some(from) = symbols_from {
+        let end = run_end(text, from, |class| class == Class::Other);
+        return run_end(text, end, |class| class == Class::Newline);
+    }
+
+    // whitespace: up to the last newline of the run if it has one
+    let run_end = run_end(text, start, is_whitespace);
+    let run = &text[start..run_end];
+    if let Some(newline) = run.rfind(['\r', '\n']) {
+        return start + newline + 1;
+    }
+    // otherwise the whole run, except for the last space when text follows
+    match run.char_indices().next_back() {
+        Some((last, _)) if run_end < text.len() && last > 0 => start + last,
+        _ => run_end,
+    }
+}
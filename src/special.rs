@@ -0,0 +1,110 @@
+// special tokens (e.g. '<|endoftext|>') are not produced by merges... they get fixed ids
+// above the merge range and are only emitted when the caller allows them in encode,
+// following RegexTokenizer.encode(text, allowed_special=...) in minbpe's regex.py
+
+use std::collections::HashSet;
+use regex::Regex;
+
+use crate::error::{Result, TokenizerError};
+use crate::training::Vocabulary;
+
+/// which registered special tokens encode may emit when their text shows up
+pub enum AllowedSpecial<'a> {
+    /// every registered special token
+    All,
+    /// none, special token text is encoded like any other text
+    None,
+    /// none, and finding special token text is an error (tiktoken's default)
+    NoneRaise,
+    /// only the listed special tokens
+    Set(HashSet<&'a str>),
+}
+
+/// a piece of text after the special tokens were split out
+pub enum Segment<'t> {
+    Text(&'t str),
+    Special(u32),
+}
+
+impl Vocabulary {
+
+    /// adds special tokens w/ their fixed ids, which have to be above every merge
+    pub fn register_special_tokens(&mut self, special_tokens: &[(&str, u32)]) -> Result<()> {
+        let first_free = self.vocab_vec.iter().map(|&(_, idx)| idx + 1).max().unwrap_or(256);
+        for &(special, idx) in special_tokens {
+            check_special_token(special)?;
+            let taken = self.special_tokens.iter().any(|(existing, existing_idx)| *existing_idx == idx && existing != special);
+            if idx < first_free || taken {
+                return Err(TokenizerError::SpecialTokenIdInUse(idx));
+            }
+            // re-registering a string moves it to the new id
+            self.special_tokens.retain(|(existing, _)| existing != special);
+            self.special_tokens.push((special.to_string(), idx));
+        }
+        Ok(())
+    }
+
+    /// id of a registered special token
+    pub fn special_token_id(&self, special: &str) -> Option<u32> {
+        self.special_tokens.iter().find(|(existing, _)| existing == special).map(|&(_, idx)| idx)
+    }
+
+    /// text of a registered special token
+    pub fn special_token_str(&self, idx: u32) -> Option<&str> {
+        self.special_tokens.iter().find(|&&(_, existing)| existing == idx).map(|(special, _)| special.as_str())
+    }
+
+    /// splits 'text' around the allowed special tokens... checks for disallowed
+    /// ones when asked to, everything else is left as plain text
+    pub fn split_special<'t>(&self, text: &'t str, allowed: &AllowedSpecial) -> Result<Vec<Segment<'t>>> {
+        let special: Vec<&(String, u32)> = match allowed {
+            AllowedSpecial::All => self.special_tokens.iter().collect(),
+            AllowedSpecial::None => Vec::new(),
+            AllowedSpecial::NoneRaise => {
+                if let Some((found, _)) = self.special_tokens.iter().find(|(special, _)| text.contains(special.as_str())) {
+                    return Err(TokenizerError::DisallowedSpecialToken(found.clone()));
+                }
+                Vec::new()
+            },
+            AllowedSpecial::Set(allowed) => {
+                if let Some(unknown) = allowed.iter().find(|special| self.special_token_id(special).is_none()) {
+                    return Err(TokenizerError::UnknownSpecialToken(unknown.to_string()));
+                }
+                self.special_tokens.iter().filter(|(special, _)| allowed.contains(special.as_str())).collect()
+            },
+        };
+        // shortcut: if no special tokens, the whole text is ordinary
+        if special.is_empty() {
+            return Ok(vec![Segment::Text(text)]);
+        }
+
+        // leftmost match wins, ties go to the token registered first (like Python's re.split)
+        let alternatives: Vec<String> = special.iter().map(|(special, _)| regex::escape(special)).collect();
+        let pattern = Regex::new(&alternatives.join("|"))?;
+        let mut segments = Vec::new();
+        let mut last_end = 0;
+        for mat in pattern.find_iter(text) {
+            if last_end != mat.start() {
+                segments.push(Segment::Text(&text[last_end..mat.start()]));
+            }
+            if let Some(&&(_, idx)) = special.iter().find(|(special, _)| special == mat.as_str()) {
+                segments.push(Segment::Special(idx));
+            }
+            last_end = mat.end();
+        }
+        if last_end < text.len() {
+            segments.push(Segment::Text(&text[last_end..]));
+        }
+        Ok(segments)
+    }
+}
+
+
+/// a special token has to be non-empty and w/o whitespace, the model file keeps it
+/// on one line next to its id
+pub(crate) fn check_special_token(special: &str) -> Result<()> {
+    if special.is_empty() || special.chars().any(char::is_whitespace) {
+        return Err(TokenizerError::InvalidSpecialToken(special.to_string()));
+    }
+    Ok(())
+}
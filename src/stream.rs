@@ -0,0 +1,70 @@
+// decoding for token-by-token generation... a multi-byte character can be split
+// across tokens, so bytes are held back until they form complete UTF-8
+
+use std::str;
+
+use crate::error::{Result, TokenizerError};
+use crate::training::Vocabulary;
+
+/// turns ids fed one at a time into text as soon as the bytes are valid UTF-8
+pub struct StreamDecoder<'v> {
+    vocab: &'v Vocabulary,
+    pending: Vec<u8>,   // bytes of an incomplete character at the end of the stream
+    position: usize,    // number of ids pushed so far, for errors
+}
+
+impl<'v> StreamDecoder<'v> {
+
+    /// a decoder w/ nothing buffered
+    pub fn new(vocab: &'v Vocabulary) -> StreamDecoder<'v> {
+        StreamDecoder { vocab, pending: Vec::new(), position: 0 }
+    }
+
+    /// add one token and return whatever text became complete (possibly "")
+    pub fn push(&mut self, id: u32) -> Result<String> {
+        let token = self.vocab.token_bytes(id)
+            .ok_or(TokenizerError::UnknownToken { id, position: self.position })?;
+        self.position += 1;
+        self.pending.extend_from_slice(token);
+        let mut text = String::new();
+        let mut start = 0;
+        loop {
+            match str::from_utf8(&self.pending[start..]) {
+                Ok(valid) => {
+                    text.push_str(valid);
+                    start = self.pending.len();
+                    break;
+                },
+                Err(err) => {
+                    let valid_up_to = start + err.valid_up_to();
+                    // the prefix was just checked by from_utf8 so nothing is replaced here
+                    text.push_str(&String::from_utf8_lossy(&self.pending[start..valid_up_to]));
+                    match err.error_len() {
+                        // bytes that can never become valid are replaced like from_utf8_lossy
+                        Some(len) => {
+                            text.push('\u{FFFD}');
+                            start = valid_up_to + len;
+                        },
+                        // an incomplete character at the end, wait for more tokens
+                        None => {
+                            start = valid_up_to;
+                            break;
+                        },
+                    }
+                },
+            }
+        }
+        self.pending.drain(..start);
+        Ok(text)
+    }
+
+    /// true when bytes of an unfinished character are waiting for the next token
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// end of the stream: an unfinished character left over becomes U+FFFD
+    pub fn finish(self) -> String {
+        String::from_utf8_lossy(&self.pending).into_owned()
+    }
+}
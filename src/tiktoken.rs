@@ -0,0 +1,162 @@
+// loads pretrained OpenAI vocabularies (e.g. cl100k_base used by GPT-4) from a local
+// tiktoken rank file so they can run through this crate without network access
+//
+// each line of the file is "<base64 token bytes> <rank>"... tiktoken only stores the
+// merged byte strings, so the pairs that made them are recovered with a small BPE run
+// the same way recover_merges() in karpathy_tokenizer/minbpe/gpt4.py does
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use crate::error::{Result, TokenizerError};
+use crate::pretokenizer;
+use crate::training::Vocabulary;
+
+/// special tokens registered on top of cl100k_base's mergeable ranks
+pub const GPT4_SPECIAL_TOKENS: [(&str, u32); 5] = [
+    ("<|endoftext|>", 100257),
+    ("<|fim_prefix|>", 100258),
+    ("<|fim_middle|>", 100259),
+    ("<|fim_suffix|>", 100260),
+    ("<|endofprompt|>", 100276),
+];
+
+
+/// loads cl100k_base.tiktoken w/ the GPT-4 split pattern and special tokens
+pub fn cl100k_base<P: AsRef<Path>>(path: P) -> Result<Vocabulary> {
+    let mut vocab = load_tiktoken(path)?;
+    vocab.register_special_tokens(&GPT4_SPECIAL_TOKENS)?;
+    Ok(vocab)
+}
+
+
+/// reads a tiktoken rank file into a Vocabulary whose token ids are the tiktoken ranks
+pub fn load_tiktoken<P: AsRef<Path>>(path: P) -> Result<Vocabulary> {
+    let contents = fs::read_to_string(path)?;
+    let mut mergeable_ranks: HashMap<Vec<u8>, u32> = HashMap::new();
+    let mut ranks = HashSet::new();
+    for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+        let mut parts = line.split_whitespace();
+        let token = parts.next().and_then(decode_base64);
+        let rank = parts.next().and_then(|rank| rank.parse::<u32>().ok());
+        match (token, rank) {
+            (Some(token), Some(rank)) => {
+                if !ranks.insert(rank) {
+                    return Err(invalid_data(format!("duplicate rank {}", rank)));
+                }
+                if mergeable_ranks.insert(token, rank).is_some() {
+                    return Err(invalid_data(format!("duplicate token at rank {}", rank)));
+                }
+            },
+            _ => return Err(invalid_data(format!("bad rank line {:?}", line))),
+        }
+    }
+    from_mergeable_ranks(&mergeable_ranks)
+}
+
+
+/// builds the Vocabulary from tiktoken's byte string -> rank table
+pub fn from_mergeable_ranks(mergeable_ranks: &HashMap<Vec<u8>, u32>) -> Result<Vocabulary> {
+    // the tokens for individual bytes are permuted... keep the permutation so
+    // text bytes can be shuffled before encoding and restored after decoding
+    let mut byte_shuffle = [0u32; 256];
+    for (byte, shuffled) in byte_shuffle.iter_mut().enumerate() {
+        *shuffled = *mergeable_ranks
+            .get(&vec![byte as u8])
+            .ok_or_else(|| invalid_data(format!("no rank for raw byte {}", byte)))?;
+    }
+
+    let mut merges = recover_merges(mergeable_ranks)?;
+    merges.sort_by_key(|&(_, rank)| rank);
+
+    let mut vocab = Vocabulary { pre_tokenizer: pretokenizer::gpt4(), ..Vocabulary::default() };
+    vocab.set_byte_shuffle(byte_shuffle);
+    for (pair, rank) in merges {
+        vocab.add_merge(pair, rank);
+    }
+    Ok(vocab)
+}
+
+
+/// the ranks are already the byte sequences in their merged state, so recover the
+/// original pairings by re-running BPE on every token until only two parts remain
+pub fn recover_merges(mergeable_ranks: &HashMap<Vec<u8>, u32>) -> Result<Vec<((u32, u32), u32)>> {
+    let mut merges = Vec::with_capacity(mergeable_ranks.len());
+    for (token, &rank) in mergeable_ranks {
+        if token.len() == 1 {
+            continue; // skip raw bytes
+        }
+        match &bpe(mergeable_ranks, token, rank)[..] {
+            [left, right] => {
+                // recover the integer ranks of the pair... the raw bytes can be
+                // missing when the table was not checked by from_mergeable_ranks()
+                match (mergeable_ranks.get(*left), mergeable_ranks.get(*right)) {
+                    (Some(&ix0), Some(&ix1)) => merges.push(((ix0, ix1), rank)),
+                    _ => return Err(invalid_data(format!("rank {} has a part w/o a rank", rank))),
+                }
+            },
+            _ => return Err(invalid_data(format!("rank {} is not the merge of two tokens", rank))),
+        }
+    }
+    Ok(merges)
+}
+
+
+/// helper for recover_merges(): merges the lowest ranked adjacent parts of 'token'
+/// but stops before any merge of rank 'max_rank' or higher
+fn bpe<'a>(mergeable_ranks: &HashMap<Vec<u8>, u32>, token: &'a [u8], max_rank: u32) -> Vec<&'a [u8]> {
+    // each part is a (start, end) range of the token
+    let mut parts: Vec<(usize, usize)> = (0..token.len()).map(|i| (i, i + 1)).collect();
+    loop {
+        let mut min_part: Option<(usize, u32)> = None;
+        for i in 0..parts.len().saturating_sub(1) {
+            let merged = &token[parts[i].0..parts[i + 1].1];
+            if let Some(&rank) = mergeable_ranks.get(merged) {
+                if min_part.is_none_or(|(_, min_rank)| rank < min_rank) {
+                    min_part = Some((i, rank));
+                }
+            }
+        }
+        match min_part {
+            Some((i, rank)) if rank < max_rank => {
+                parts[i].1 = parts[i + 1].1;
+                parts.remove(i + 1);
+            },
+            _ => break,
+        }
+    }
+    parts.into_iter().map(|(start, end)| &token[start..end]).collect()
+}
+
+
+/// helper for load_tiktoken(): standard (padded) base64 to bytes
+fn decode_base64(encoded: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(encoded.len() * 3 / 4);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in encoded.trim_end_matches('=').bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(bytes)
+}
+
+
+/// helper for the loaders: a malformed rank file
+fn invalid_data(message: String) -> TokenizerError {
+    TokenizerError::CorruptModel(message)
+}
@@ -2,18 +2,106 @@
 // engineer training methods... tiktoken/tiktoken_ext/openai_public.py shows some details
 // tiktokenizer.vercel.app to see results with different versions of tokenizers
 
-use std::fs::File;
-use std::io::Write;
-use std::collections::HashMap;
-use regex::Regex;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::error::{Result, TokenizerError};
+use crate::normalizer::Normalizer;
+use crate::observer::{NoObserver, TrainingObserver};
+use crate::pretokenizer::{self, PreTokenizer};
+use crate::special;
 
 pub struct Vocabulary {
-    pub vocab_hash: HashMap<u32, (u32, u32)>,    // for decoding
-    pub vocab_vec: Vec<((u32, u32), u32)>,      // for encoding
+    pub vocab_hash: HashMap<u32, (u32, u32)>,    // new word -> the pair it was merged from
+    pub vocab_vec: Vec<((u32, u32), u32)>,      // merges in the order they were learned
+    pub vocab_ranks: HashMap<(u32, u32), u32>,  // for encoding (pair -> new word, lower is earlier)
+    pub normalizer: Normalizer,                 // runs before the split, also kept in the model file
+    pub pre_tokenizer: Arc<dyn PreTokenizer>,   // how text is split, its pattern is kept in the model file
+    pub special_tokens: Vec<(String, u32)>,     // special token strings and their ids
+    pub byte_shuffle: Option<[u32; 256]>,       // raw byte -> token id when not the identity (tiktoken)
+    pub id_to_bytes: Vec<Vec<u8>>,              // for decoding: token id -> its bytes (empty when unused)
+}
+
+impl Default for Vocabulary {
+    fn default() -> Vocabulary {
+        Vocabulary {
+            vocab_hash: HashMap::new(),
+            vocab_vec: Vec::new(),
+            vocab_ranks: HashMap::new(),
+            normalizer: Normalizer::default(),
+            pre_tokenizer: pretokenizer::gpt4(),
+            special_tokens: Vec::new(),
+            byte_shuffle: None,
+            id_to_bytes: (0..=255u8).map(|byte| vec![byte]).collect(),
+        }
+    }
 }
 
 impl Vocabulary {
 
+    /// an empty vocabulary holding only the 256 raw byte tokens
+    pub fn new() -> Vocabulary {
+        Vocabulary::default()
+    }
+
+    /// one past the highest id, the size of a table indexed by token id... ids can have
+    /// gaps (cl100k's special tokens start at 100257) so this is not a count of the tokens
+    pub fn len(&self) -> usize {
+        self.special_tokens
+            .iter()
+            .map(|&(_, id)| id as usize + 1)
+            .fold(256 + self.vocab_vec.len(), usize::max)
+    }
+
+    /// never true, the 256 raw byte tokens are always there... see has_merges()
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// false until merges are learned or loaded
+    pub fn has_merges(&self) -> bool {
+        !self.vocab_vec.is_empty()
+    }
+
+    /// record a merge of 'pair' into the token 'new_word'
+    pub fn add_merge(&mut self, pair: (u32, u32), new_word: u32) {
+        self.vocab_vec.push((pair, new_word));
+        self.vocab_hash.insert(new_word, pair);
+        self.vocab_ranks.insert(pair, new_word);
+
+        // the new token's bytes are its children's bytes, both already in the table
+        let mut bytes = self.token_bytes(pair.0).unwrap_or_default().to_vec();
+        bytes.extend_from_slice(self.token_bytes(pair.1).unwrap_or_default());
+        if self.id_to_bytes.len() <= new_word as usize {
+            self.id_to_bytes.resize(new_word as usize + 1, Vec::new());
+        }
+        self.id_to_bytes[new_word as usize] = bytes;
+    }
+
+    /// use a permutation of the raw byte tokens (raw byte -> token id), before any merge is added
+    pub fn set_byte_shuffle(&mut self, byte_shuffle: [u32; 256]) {
+        self.byte_shuffle = Some(byte_shuffle);
+        self.reset_raw_byte_rows();
+    }
+
+    /// token id of a single raw byte (the byte itself unless the vocabulary is shuffled)
+    pub fn byte_token(&self, byte: u8) -> u32 {
+        match &self.byte_shuffle {
+            Some(shuffle) => shuffle[byte as usize],
+            None => byte as u32,
+        }
+    }
+
+    /// inverse of byte_token() for all 256 raw byte tokens
+    pub fn token_bytes_table(&self) -> [u8; 256] {
+        let mut table = [0u8; 256];
+        for byte in 0..=255u8 {
+            table[self.byte_token(byte) as usize] = byte;
+        }
+        table
+    }
+
     /// convert a sequence of u32 values to a UTF-8 encoded string
     /// w/ special handling for control characters and invalid sequences
     pub fn stringify_word(&self, bytes: &[u32]) -> String {
@@ -34,7 +122,7 @@ impl Vocabulary {
 
             // valid utf-8 character then push
             } else if *byte < 0x7F {
-                result_string.push(*byte as u8 as char);
+                result_string.push(*byte as char);
                 last_was_invalid = false;
 
             //  invalid utf-8 then replace once
@@ -49,230 +137,438 @@ impl Vocabulary {
         result_string
     }
 
-    /// helper for stringify_word()... expand bytes that have 
-    /// been encoded into u32 values to get the original sequence
-    fn expand_bytes(&self, bytes: &[u32]) -> Vec<u8> {
+    /// the bytes token 'id' stands for (merges, raw bytes and special tokens)
+    pub fn token_bytes(&self, id: u32) -> Option<&[u8]> {
+        match self.id_to_bytes.get(id as usize) {
+            Some(bytes) if !bytes.is_empty() => Some(bytes),
+            _ => self.special_token_str(id).map(str::as_bytes),
+        }
+    }
+
+    /// helper for stringify_word() and the decoders... the original byte
+    /// sequence of the ids, looked up in id_to_bytes (unknown ids are skipped)
+    pub(crate) fn expand_bytes(&self, bytes: &[u32]) -> Vec<u8> {
         let mut result: Vec<u8> = Vec::new();
-        
         for &byte in bytes {
-            // directly convert
-            if byte <= 255 {
-                result.push(byte as u8);
-            
-            // recursively unpack the "extended byte" into merged u8 values using the mapping
-            } else {
-                if let Some(&(byte1, byte2)) = self.vocab_hash.get(&byte) {
-                    let expanded_bytes1 = self.expand_bytes(&[byte1]);
-                    let expanded_bytes2 = self.expand_bytes(&[byte2]);
-                    result.extend(expanded_bytes1);
-                    result.extend(expanded_bytes2);
-                }
+            if let Some(expanded) = self.token_bytes(byte) {
+                result.extend_from_slice(expanded);
             }
         }
-
         result
     }
-    
+
+    /// helper for set_byte_shuffle(): rewrites the rows of the 256 raw byte tokens
+    fn reset_raw_byte_rows(&mut self) {
+        if self.id_to_bytes.len() < 256 {
+            self.id_to_bytes.resize(256, Vec::new());
+        }
+        let table = self.token_bytes_table();
+        for (id, &byte) in table.iter().enumerate() {
+            self.id_to_bytes[id] = vec![byte];
+        }
+    }
+}
+
+
+/// how much training prints to stdout
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum Verbosity {
+    Quiet,
+    /// one line once training is done
+    Summary,
+    /// a line for every merge as well
+    Merges,
+}
+
+
+/// the knobs of a training run
+#[derive(Clone, Debug)]
+pub struct TrainerConfig {
+    /// target number of tokens: 256 raw bytes + merges + special tokens
+    pub vocab_size: u32,
+    /// pairs seen fewer times than this are never merged
+    pub min_pair_frequency: u64,
+    /// merges producing tokens longer than this many bytes are skipped
+    pub max_token_length: Option<usize>,
+    pub verbosity: Verbosity,
+    /// applied to the text before it is split, saved w/ the vocabulary
+    pub normalizer: Normalizer,
+    /// how the text is cut into chunks, saved w/ the vocabulary
+    pub pre_tokenizer: Arc<dyn PreTokenizer>,
+    /// registered w/ the ids right after the last merge
+    pub special_tokens: Vec<String>,
+}
+
+impl Default for TrainerConfig {
+    fn default() -> TrainerConfig {
+        TrainerConfig {
+            vocab_size: 512,
+            min_pair_frequency: 2,
+            max_token_length: None,
+            verbosity: Verbosity::Summary,
+            normalizer: Normalizer::default(),
+            pre_tokenizer: pretokenizer::gpt4(),
+            special_tokens: Vec::new(),
+        }
+    }
 }
 
 
 // instead of using the raw UTF-8 bytes we want to support a larger vocabulary size
 // that we can tune as a hyperparameter while sticking with the same encoding
-pub fn execute(test_string: &str, verbose: bool) -> Vocabulary {
-    let target_vocab_size = 512;
-    let new_vocabulary = train_tokenizer(test_string, target_vocab_size, verbose);
-    
-    new_vocabulary
+pub fn execute(test_string: &str, verbose: bool) -> Result<Vocabulary> {
+    let config = TrainerConfig {
+        verbosity: if verbose { Verbosity::Merges } else { Verbosity::Summary },
+        ..TrainerConfig::default()
+    };
+    train_tokenizer(test_string, &config, &mut NoObserver)
 }
 
 
 /// uses the BPE algorithm to merge the most common pairs of bytes across chunks of the input text
-/// the number of merges depends on the desired 'target' words in the returned Vocabulary object
-fn train_tokenizer(text: &str, target: u32, verbose: bool) -> Vocabulary {
-    let mut file = File::create("data/output/train_output.txt").unwrap();
-    let mut file2 = File::create("data/output/chunk_output.txt").unwrap();
+/// the number of merges depends on the desired 'vocab_size' in the config
+pub fn train_tokenizer(text: &str, config: &TrainerConfig, observer: &mut dyn TrainingObserver) -> Result<Vocabulary> {
+    check_special_tokens(config)?;
+    // split and convert to bytes straight away, w/o holding the chunks as strings...
+    // every occurrence of a chunk is its own entry
+    let text = config.normalizer.normalize(text);
+    let mut split_bytes_ext: Vec<(Vec<u32>, u64)> = Vec::new();
+    for chunk in config.pre_tokenizer.chunks(&text) {
+        observer.chunk_produced(chunk);
+        split_bytes_ext.push((chunk.bytes().map(|b| b as u32).collect(), 1));
+    }
+
+    learn_merges(split_bytes_ext, config, observer)
+}
+
+
+/// same merges as train_tokenizer() but each distinct chunk is processed once and
+/// its pairs weighted by how often it occurs, see count_chunks()... the chunks reach
+/// 'observer' in sorted order so a trace doesn't depend on the HashMap
+pub fn train_from_chunk_counts(
+    chunk_counts: &HashMap<String, u64>,
+    config: &TrainerConfig,
+    observer: &mut dyn TrainingObserver,
+) -> Result<Vocabulary> {
+    check_special_tokens(config)?;
+    let mut sorted_chunks: Vec<(&String, &u64)> = chunk_counts.iter().collect();
+    sorted_chunks.sort_unstable();
+    let mut weighted_chunks: Vec<(Vec<u32>, u64)> = Vec::with_capacity(sorted_chunks.len());
+    for (chunk, &count) in sorted_chunks {
+        observer.chunk_produced(chunk);
+        weighted_chunks.push((chunk.bytes().map(|b| b as u32).collect(), count));
+    }
+
+    learn_merges(weighted_chunks, config, observer)
+}
+
+
+/// collapses the chunks of the text into a table of distinct chunk -> occurrences, normalized
+/// and split the way 'config' says (pass the same config to train_from_chunk_counts())
+pub fn count_chunks(text: &str, config: &TrainerConfig) -> HashMap<String, u64> {
+    let mut chunk_counts: HashMap<String, u64> = HashMap::new();
+    add_chunk_counts(&mut chunk_counts, text, config);
+    chunk_counts
+}
+
+
+/// count_chunks() adding to an existing table, e.g. one document of a corpus at a time
+pub fn add_chunk_counts(chunk_counts: &mut HashMap<String, u64>, text: &str, config: &TrainerConfig) {
+    let text = config.normalizer.normalize(text);
+    for chunk in config.pre_tokenizer.chunks(&text) {
+        // only a chunk seen for the first time is copied
+        match chunk_counts.get_mut(chunk) {
+            Some(count) => *count += 1,
+            None => { chunk_counts.insert(chunk.to_string(), 1); },
+        }
+    }
+}
+
+
+/// helper for the training paths: rejects empty, whitespace and repeated special tokens
+/// before any time is spent on the merges
+pub(crate) fn check_special_tokens(config: &TrainerConfig) -> Result<()> {
+    for (i, special) in config.special_tokens.iter().enumerate() {
+        special::check_special_token(special)?;
+        if config.special_tokens[..i].contains(special) {
+            return Err(TokenizerError::InvalidSpecialToken(special.clone()));
+        }
+    }
+    Ok(())
+}
+
+
+/// helper for the training paths: merges the most common pair of the (chunk, weight)
+/// entries until the target is reached, reporting each merge to 'observer'
+fn learn_merges(chunks: Vec<(Vec<u32>, u64)>, config: &TrainerConfig, observer: &mut dyn TrainingObserver) -> Result<Vocabulary> {
     let mut vocab = Vocabulary {
-        vocab_hash: HashMap::<u32, (u32, u32)>::new(),
-        vocab_vec: Vec::<((u32, u32), u32)>::new(),
+        normalizer: config.normalizer.clone(),
+        pre_tokenizer: config.pre_tokenizer.clone(),
+        ..Vocabulary::default()
     };
     // start with 256 as the first new 'word' after the initial byte range
     let mut new_word: u32 = 256;
-    let total_merges = target-new_word;
-
-    // split, convert to bytes, then extend the bytes to hold new words
-    let split_text: Vec<String> = split(text);
-    
-    // for checking chunking
-    for chunk in &split_text {
-        writeln!(file2, "{}", chunk).unwrap();
-    }
-    
-    let mut split_bytes_ext: Vec<Vec<u32>> = 
-        split_text
-            .iter()
-            .map(|s| s.as_bytes().iter().map(|&b| b as u32).collect())
-            .collect();
+    let total_merges = config.vocab_size.saturating_sub(new_word + config.special_tokens.len() as u32);
+    // byte length of every token so far, for max_token_length
+    let mut token_lengths: Vec<usize> = vec![1; 256];
+    let eligible = |(byte1, byte2): (u32, u32), token_lengths: &Vec<usize>| {
+        config.max_token_length
+            .is_none_or(|max| token_lengths[byte1 as usize] + token_lengths[byte2 as usize] <= max)
+    };
 
-    let mut pairs: HashMap<(u32, u32), u32> = HashMap::new();
+    // counts of every pair across all chunks, updated after each merge instead of recounted
+    let mut pairs = PairIndex::new(chunks);
     let mut merges = total_merges;
     // for each loop.. merge 1 byte sequence and get 1 new word
     while merges > 0 {
-        pairs.clear();
-        // to determine what to merge.. find the most common pair of 
-        // consecutive bytes when each text chunk is considered separately
-        for chunk in &split_bytes_ext {
-            let chunk_pairs = pair_counts(chunk);
-            for (pair, count) in chunk_pairs {
-                *pairs.entry(pair).or_insert(0) += count;
-            }
-        }
-
-        if let Some(((byte1, byte2), count)) = pairs.iter()
-            // find the pair with the maximum count (at least 2)
-            // ties are broken by lexiographical order
-                .filter(|&(_, count)| *count >= 2)
-                .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
-                .map(|(pair, &count)| (*pair, count)) {
+        // find the pair with the maximum count (at least min_pair_frequency)
+        // ties are broken by lexiographical order
+        if let Some(((byte1, byte2), count)) = pairs.most_common(config.min_pair_frequency, |pair| eligible(pair, &token_lengths)) {
 
             // update Vocabulary
-            vocab.vocab_vec.push(((byte1, byte2), new_word));
-            vocab.vocab_hash.insert(new_word, (byte1, byte2));
+            vocab.add_merge((byte1, byte2), new_word);
+            token_lengths.push(token_lengths[byte1 as usize] + token_lengths[byte2 as usize]);
             
             // print most common pair found across all chunks and the new word
-            let string_view = vocab.stringify_word(&[byte1, byte2]);
-            if verbose == true {
+            if config.verbosity >= Verbosity::Merges {
+                let string_view = vocab.stringify_word(&[byte1, byte2]);
                 println!("merge {}/{}: ({}, {}) -> {} (b'{}') had {} occurrences",
                     total_merges-merges+1, total_merges, byte1, byte2, new_word, string_view, count);
              }
-            writeln!(file, "[{}][{}] -> [{}] {}", 
-                vocab.stringify_word(&[byte1]), 
-                vocab.stringify_word(&[byte2]), 
-                &string_view, 
-                &new_word)
-            .unwrap();
-
-            // replace all occurrences of pair in each chunk with new_word
-            for chunk in &mut split_bytes_ext {
-                let mut i = 0;
-                while i < chunk.len() - 1 {
-                    // find new way to update.. inefficient here
-                    if chunk[i] == byte1 && chunk[i + 1] == byte2 {
-                        chunk[i] = new_word;
-                        chunk.remove(i + 1);
-                    } else {
-                        i += 1;
-                    }
-                }
-            }
-           new_word += 1; // prepare the new word for the next iteration
+            observer.merge_performed(&vocab, (byte1, byte2), new_word, count);
+
+            // replace all occurrences of pair in the chunks that hold it with new_word
+            pairs.merge((byte1, byte2), new_word);
+            new_word += 1; // prepare the new word for the next iteration
         } else {
            break;  // break if no more pairs are found
         }
         merges -= 1;
     }
-    println!("extended vocabulary by {} from 256 words to {}", new_word-256, new_word); 
+    if config.verbosity >= Verbosity::Summary {
+        println!("extended vocabulary by {} from 256 words to {}", new_word-256, new_word); 
+    }
+
+    // special tokens take the ids right after the merges
+    let special_tokens: Vec<(&str, u32)> = config.special_tokens.iter()
+        .zip(new_word..)
+        .map(|(special, idx)| (special.as_str(), idx))
+        .collect();
+    vocab.register_special_tokens(&special_tokens)?;
+    observer.training_finished(&vocab);
     
-    vocab
+    Ok(vocab)
 }
 
 
-/// returns counts for consecutive element pairs' occurrences.
-pub fn pair_counts(input_vec: &Vec<u32>) -> HashMap<(u32, u32), u32> {
-    
-    let mut pair_counts = HashMap::new();
-    for window in input_vec.windows(2) {
-        if let [a, b] = *window {
-            let pair = ((a, b), 1);
-            *pair_counts.entry(pair.0).or_insert(0) += pair.1;
+/// keeps the pair counts of a set of chunks current while merges are applied:
+/// each pair knows which chunks hold it and a max-heap yields the most common one,
+/// so a merge only revisits the chunks it touches rather than the whole text
+struct PairIndex {
+    chunks: Vec<Vec<u32>>,
+    weights: Vec<u64>,
+    counts: HashMap<(u32, u32), u64>,
+    positions: HashMap<(u32, u32), HashSet<usize>>,
+    // (count, pair) entries... an entry is stale once its count no longer matches 'counts'
+    heap: BinaryHeap<(u64, Reverse<(u32, u32)>)>,
+}
+
+impl PairIndex {
+
+    fn new(weighted_chunks: Vec<(Vec<u32>, u64)>) -> PairIndex {
+        let (chunks, weights): (Vec<Vec<u32>>, Vec<u64>) = weighted_chunks.into_iter().unzip();
+        let (counts, positions) = count_pairs(&chunks, &weights);
+        let heap = counts.iter().map(|(&pair, &count)| (count, Reverse(pair))).collect();
+
+        PairIndex { chunks, weights, counts, positions, heap }
+    }
+
+    /// the pair w/ the highest count (ties go to the smallest pair) if it
+    /// occurs at least 'min_count' times... pairs that are not 'eligible' are
+    /// dropped until a merge makes them more common again
+    fn most_common(&mut self, min_count: u64, eligible: impl Fn((u32, u32)) -> bool) -> Option<((u32, u32), u64)> {
+        while let Some((count, Reverse(pair))) = self.heap.pop() {
+            let current = self.counts.get(&pair).copied().unwrap_or(0);
+            if count == current {
+                if count < min_count {
+                    self.heap.push((count, Reverse(pair)));
+                    return None;
+                }
+                if !eligible(pair) {
+                    continue;
+                }
+                return Some((pair, count));
+            }
+            // stale entry: requeue w/ the up to date count
+            if current > 0 {
+                self.heap.push((current, Reverse(pair)));
+            }
         }
+        None
     }
 
-    pair_counts
+    /// replaces 'pair' w/ 'new_word' in every chunk holding it and
+    /// applies the resulting changes in neighbouring pair counts
+    fn merge(&mut self, pair: (u32, u32), new_word: u32) {
+        let indices: Vec<usize> = self.positions.remove(&pair).unwrap_or_default().into_iter().collect();
+        let mut delta: HashMap<(u32, u32), i64> = HashMap::new();
+        for shard in merge_chunks(&self.chunks, &self.weights, &indices, pair, new_word) {
+            for (changed, change) in shard.delta {
+                *delta.entry(changed).or_insert(0) += change;
+            }
+            for (added, index) in shard.positions {
+                self.positions.entry(added).or_default().insert(index);
+            }
+            for (index, merged) in shard.chunks {
+                self.chunks[index] = merged;
+            }
+        }
+
+        // requeue the pairs that became more common, decreases are caught in most_common()
+        for (changed, change) in delta {
+            let count = self.counts.entry(changed).or_insert(0);
+            *count = (*count as i64 + change) as u64;
+            if change > 0 {
+                self.heap.push((*count, Reverse(changed)));
+            }
+        }
+        self.counts.remove(&pair);
+    }
 }
 
 
-/// splits text into chunks and returns those in a vector
-pub fn split(text: &str)  -> Vec<String> {
-    // adaptation of pattern used for GPT-4 tokenizer
-    let combined_pattern = format!(
-        r"{}|{}|{}|{}|{}|{}",
-        r"(?i:'(?:[sdmt]|ll|ve|re))",               // contractions (new syntax, but should match?)
-        r"[^\r\n\p{L}\p{N}]\p{L}+|^\p{L}+",         // words (does not include ?+, tried to recreate)
-        r"\p{N}{1,3}",                              // numbers (same syntax)
-        r" ?[^\s\p{L}\p{N}]+[\r\n]*",              // special characters  (+ instead of ++, but should match?)
-        r"\s*[\r\n]",                              // newlines (same syntax)
-        r"\s+"                                     // spaces (matches after adjust_whitespace())
-    );
-    let regex = Regex::new(&combined_pattern).unwrap();
-
-    let mut results = Vec::new();
-    let mut last_end = 0;
-    for mat in regex.find_iter(text) {
-        if last_end != mat.start() {
-            // push text between matches
-            results.push(text[last_end..mat.start()].to_string());
+type PairCounts = HashMap<(u32, u32), u64>;
+type PairPositions = HashMap<(u32, u32), HashSet<usize>>;
+
+/// helper for PairIndex::new(): the weighted count of every pair and the chunks holding it...
+/// w/ the "parallel" feature each thread counts a shard of the chunks and the shards are
+/// added up, sums do not depend on the order so the counts are those of a single thread
+fn count_pairs(chunks: &[Vec<u32>], weights: &[u64]) -> (PairCounts, PairPositions) {
+    #[cfg(feature = "parallel")]
+    if chunks.len() >= PARALLEL_MIN_CHUNKS {
+        use rayon::prelude::*;
+        let shard_size = chunks.len().div_ceil(rayon::current_num_threads());
+        return chunks
+            .par_chunks(shard_size)
+            .enumerate()
+            .map(|(shard, part)| count_shard(part, weights, shard * shard_size))
+            .reduce(Default::default, |(mut counts, mut positions), (more_counts, more_positions)| {
+                for (pair, count) in more_counts {
+                    *counts.entry(pair).or_insert(0) += count;
+                }
+                for (pair, indices) in more_positions {
+                    positions.entry(pair).or_default().extend(indices);
+                }
+                (counts, positions)
+            });
+    }
+    count_shard(chunks, weights, 0)
+}
+
+/// helper for count_pairs(): the pairs of the chunks starting at index 'offset'
+fn count_shard(chunks: &[Vec<u32>], weights: &[u64], offset: usize) -> (PairCounts, PairPositions) {
+    let mut counts: PairCounts = HashMap::new();
+    let mut positions: PairPositions = HashMap::new();
+    for (index, chunk) in (offset..).zip(chunks) {
+        for window in chunk.windows(2) {
+            let pair = (window[0], window[1]);
+            *counts.entry(pair).or_insert(0) += weights[index];
+            positions.entry(pair).or_default().insert(index);
         }
-        // push match
-        results.push(mat.as_str().to_string());
-        last_end = mat.end();
     }
-    // push any remaining text after last match
-    if last_end < text.len() {
-        results.push(text[last_end..].to_string());
+    (counts, positions)
+}
+
+
+/// fewer chunks than this to count or merge are not worth handing to other threads
+#[cfg(feature = "parallel")]
+const PARALLEL_MIN_CHUNKS: usize = 4096;
+
+/// what merging a pair did to some of the chunks, see merge_chunks()
+struct MergedShard {
+    chunks: Vec<(usize, Vec<u32>)>,             // index -> the chunk after the merge
+    delta: HashMap<(u32, u32), i64>,            // change in the weighted pair counts
+    positions: Vec<((u32, u32), usize)>,        // pairs w/ the new word and the chunk holding them
+}
+
+/// helper for PairIndex::merge(): the pair merged in the chunks at 'indices'... like
+/// count_pairs() the work is sharded w/ the "parallel" feature and the shards only
+/// differ in the order their changes are applied, which does not change the result
+fn merge_chunks(chunks: &[Vec<u32>], weights: &[u64], indices: &[usize], pair: (u32, u32), new_word: u32) -> Vec<MergedShard> {
+    #[cfg(feature = "parallel")]
+    if indices.len() >= PARALLEL_MIN_CHUNKS {
+        use rayon::prelude::*;
+        let shard_size = indices.len().div_ceil(rayon::current_num_threads());
+        return indices
+            .par_chunks(shard_size)
+            .map(|part| merge_shard(chunks, weights, part, pair, new_word))
+            .collect();
     }
+    vec![merge_shard(chunks, weights, indices, pair, new_word)]
+}
 
-    adjust_whitespace(results)
+/// helper for merge_chunks(): one thread's share of the chunks
+fn merge_shard(chunks: &[Vec<u32>], weights: &[u64], indices: &[usize], pair: (u32, u32), new_word: u32) -> MergedShard {
+    let mut shard = MergedShard { chunks: Vec::new(), delta: HashMap::new(), positions: Vec::new() };
+    for &index in indices {
+        let chunk = &chunks[index];
+        if !chunk.windows(2).any(|window| (window[0], window[1]) == pair) {
+            continue;  // the pair was already merged away in this chunk
+        }
+        let merged = merge(chunk, pair, new_word);
+        let weight = weights[index] as i64;
+
+        // every pair of the chunk before the merge goes away, those after come back
+        for window in chunk.windows(2) {
+            *shard.delta.entry((window[0], window[1])).or_insert(0) -= weight;
+        }
+        for window in merged.windows(2) {
+            *shard.delta.entry((window[0], window[1])).or_insert(0) += weight;
+            // only pairs w/ the new word can be new to this chunk
+            if window[0] == new_word || window[1] == new_word {
+                shard.positions.push(((window[0], window[1]), index));
+            }
+        }
+        shard.chunks.push((index, merged));
+    }
+    shard
 }
 
 
-/// helper for split(): handles the negative lookahead for white-space pattern: "\s+(?!\S)"
-/// in the context of our pattern in split() since Rust Regex impl does not support
-fn adjust_whitespace(tokens: Vec<String>) -> Vec<String> {
-    let mut adjusted_tokens = Vec::new();
-    let mut previous_was_whitespace = false;
-    let is_whitespace = |s: &str| s.chars().all(|c| c == ' ' || c == '\t');
+/// returns counts for consecutive element pairs' occurrences.
+pub fn pair_counts(input_vec: &[u32]) -> HashMap<(u32, u32), u32> {
     
-    for (i, token) in tokens.iter().enumerate() {
-
-        // check if all whitespace
-        if is_whitespace(token) {
-            // check if next token starts w/ non-whitespace (negative lookahead)
-            if i + 1 < tokens.len() && tokens[i + 1].starts_with(|c: char| !c.is_whitespace()) {
-                // push all but the last whitespace, which
-                // should be at the start of the next token
-                if token.len() > 1 {
-                    previous_was_whitespace = true;
-                    adjusted_tokens.push(token[0..token.len() - 1].to_string());
-                // no changes
-                } else {
-                    adjusted_tokens.push(token.clone());
-                }
-            // no changes
-            } else {
-                adjusted_tokens.push(token.clone());
-            }
-        
-        // not all whitespace
-        } else {
-            // include the trailing whitespace from the
-            // previous token at the beginning of this token
-            if previous_was_whitespace {
-                let new_token = format!(" {}", token);
-                adjusted_tokens.push(new_token);
-                previous_was_whitespace = false;
-            // no changes
-            } else {
-                adjusted_tokens.push(token.clone());
-            }
+    let mut pair_counts = HashMap::new();
+    for window in input_vec.windows(2) {
+        if let [a, b] = *window {
+            let pair = ((a, b), 1);
+            *pair_counts.entry(pair.0).or_insert(0) += pair.1;
         }
     }
 
-    adjusted_tokens
+    pair_counts
+}
+
+
+/// replaces every non-overlapping occurrence of 'pair' (left to right) w/ 'new_word'
+pub fn merge(ids: &[u32], pair: (u32, u32), new_word: u32) -> Vec<u32> {
+    let mut merged = Vec::with_capacity(ids.len());
+    let mut i = 0;
+    while i < ids.len() {
+        if i + 1 < ids.len() && (ids[i], ids[i + 1]) == pair {
+            merged.push(new_word);
+            i += 2;
+        } else {
+            merged.push(ids[i]);
+            i += 1;
+        }
+    }
+    merged
 }
 
 
-// TODO: incorporate special tokens after researching & determining what's needed
-//       (requires changes to transformer's code to account for new special tokens)
+// NOTE: special tokens are registered on the Vocabulary and emitted by encode_special()
+//       (see special.rs)... the transformer's code still has to account for them
 
 // TODO: explore new types of tokens like those in 
 //       "Learning to Compress Prompts with Gist Tokens" by Jesse Mu, Xiang Lisa Li, Noah Goodman
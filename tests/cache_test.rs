@@ -0,0 +1,78 @@
+mod common;
+
+use std::fs;
+
+use common::trained;
+use tokenizer::{BpeTokenizer, Tokenizer};
+
+
+#[test]
+fn cached_ids_are_the_same() {
+    let plain = trained(400);
+    let mut cached = trained(400);
+    cached.set_cache_capacity(1000);
+    assert!(plain.cache_stats().is_none());
+
+    let text = fs::read_to_string("data/encode_text.txt").unwrap();
+    let expected = plain.encode(&text).unwrap();
+    assert_eq!(cached.encode(&text).unwrap(), expected);
+    let first = cached.cache_stats().unwrap();
+    assert!(first.hits > 0 && first.misses > 0);
+
+    // the second time every chunk is known
+    assert_eq!(cached.encode(&text).unwrap(), expected);
+    let second = cached.cache_stats().unwrap();
+    assert_eq!(second.misses, first.misses);
+    assert_eq!(second.hits, first.hits + first.hits + first.misses);
+    assert!(second.hit_rate() > first.hit_rate());
+}
+
+
+#[test]
+fn least_recently_used_chunks_are_dropped() {
+    let mut tokenizer = BpeTokenizer::new();
+    tokenizer.set_cache_capacity(2);
+    tokenizer.encode("one two").unwrap();       // "one", " two"
+    tokenizer.encode("one three").unwrap();     // "one" is used again, " two" goes for " three"
+    let stats = tokenizer.cache_stats().unwrap();
+    assert_eq!((stats.hits, stats.misses, stats.entries, stats.capacity), (1, 3, 2, 2));
+
+    tokenizer.encode("one two").unwrap();
+    assert_eq!(tokenizer.cache_stats().unwrap().hits, 2);
+    assert_eq!(tokenizer.cache_stats().unwrap().misses, 4);
+
+    tokenizer.set_cache_capacity(0);
+    assert!(tokenizer.cache_stats().is_none());
+}
+
+
+#[test]
+fn training_empties_the_cache() {
+    let mut tokenizer = BpeTokenizer::new();
+    tokenizer.set_cache_capacity(100);
+    let raw = tokenizer.encode("hello hello").unwrap();
+    assert_eq!(raw.len(), 11);
+
+    tokenizer.train("hello hello hello hello", 260, false).unwrap();
+    assert_eq!(tokenizer.cache_stats().unwrap().entries, 0);
+    assert!(tokenizer.encode("hello hello").unwrap().len() < raw.len());
+}
+
+
+#[test]
+fn cache_is_shared_between_threads() {
+    let mut tokenizer = trained(400);
+    tokenizer.set_cache_capacity(1000);
+    let text = fs::read_to_string("data/encode_text.txt").unwrap();
+    let expected = trained(400).encode(&text).unwrap();
+    let chunks = tokenizer.vocab.pre_tokenizer.chunks(&text).count() as u64;
+
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| assert_eq!(tokenizer.encode(&text).unwrap(), expected));
+        }
+    });
+    let stats = tokenizer.cache_stats().unwrap();
+    assert_eq!(stats.hits + stats.misses, 4 * chunks);
+    assert!(stats.entries as u64 <= chunks);
+}
@@ -0,0 +1,96 @@
+// the tokenizer binary end to end: arguments, files, stdin/stdout and the exit status
+
+mod common;
+
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+use common::temp_dir;
+
+/// runs the binary w/ 'args' and 'stdin' as its input
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_tokenizer"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+
+#[test]
+fn train_encode_decode_round_trip() {
+    let dir = temp_dir("cli_round_trip");
+    let prefix = dir.join("tiny");
+    let prefix = prefix.to_str().unwrap();
+    let trained = run(&["train", "--input", "data/train_text.txt", "--out", prefix, "--vocab-size", "300"], "");
+    assert!(trained.status.success(), "{}", String::from_utf8_lossy(&trained.stderr));
+    let model = format!("{}.model", prefix);
+    assert!(fs::metadata(&model).is_ok() && fs::metadata(format!("{}.vocab", prefix)).is_ok());
+
+    // encode reads stdin, decode reads the ids back from a file
+    let text = "there were the other 2020 events, naïve café 🦀\n";
+    let encoded = run(&["encode", "--model", &model], text);
+    assert!(encoded.status.success(), "{}", String::from_utf8_lossy(&encoded.stderr));
+    let ids = String::from_utf8(encoded.stdout).unwrap();
+    assert!(ids.split_whitespace().count() < text.len(), "some merges apply: {}", ids);
+    let ids_file = dir.join("ids.txt");
+    fs::write(&ids_file, &ids).unwrap();
+    let decoded = run(&["decode", "--model", &model, "--input", ids_file.to_str().unwrap()], "");
+    assert!(decoded.status.success(), "{}", String::from_utf8_lossy(&decoded.stderr));
+    assert_eq!(String::from_utf8(decoded.stdout).unwrap(), text);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+
+#[test]
+fn decode_fails_on_unknown_id() {
+    let dir = temp_dir("cli_unknown_id");
+    let model = dir.join("bytes.model");
+    // a minbpe model w/o merges, so ids stop at 255
+    fs::write(&model, "minbpe v1\n\n0\n").unwrap();
+    let decoded = run(&["decode", "--model", model.to_str().unwrap()], "104 105 4000");
+    assert_eq!(decoded.status.code(), Some(1));
+    assert!(decoded.stdout.is_empty());
+    let stderr = String::from_utf8(decoded.stderr).unwrap();
+    assert!(stderr.starts_with("error: unknown token id 4000 at position 2"), "{}", stderr);
+
+    // --lossy replaces it instead
+    let lossy = run(&["decode", "--model", model.to_str().unwrap(), "--lossy"], "104 105 4000");
+    assert!(lossy.status.success());
+    assert_eq!(String::from_utf8(lossy.stdout).unwrap(), "hi\u{fffd}");
+    fs::remove_dir_all(dir).unwrap();
+}
+
+
+#[test]
+fn train_requires_out() {
+    let trained = run(&["train", "--input", "data/train_text.txt"], "");
+    assert_eq!(trained.status.code(), Some(1));
+    let stderr = String::from_utf8(trained.stderr).unwrap();
+    assert!(stderr.starts_with("error: --out is required"), "{}", stderr);
+    assert!(stderr.contains("usage: tokenizer <command> [options]"));
+}
+
+
+#[test]
+fn unknown_options_are_rejected() {
+    let encoded = run(&["encode", "--model", "data/output/tokenizer.model", "--lossy"], "");
+    assert_eq!(encoded.status.code(), Some(1));
+    let stderr = String::from_utf8(encoded.stderr).unwrap();
+    assert!(stderr.starts_with("error: unknown option --lossy"), "{}", stderr);
+    assert!(stderr.contains("usage: tokenizer <command> [options]"));
+}
+
+
+#[test]
+fn train_input_excludes_a_corpus() {
+    let trained = run(&["train", "--input", "data/train_text.txt", "--lines", "data/train_text.txt", "--out", "unused"], "");
+    assert_eq!(trained.status.code(), Some(1));
+    let stderr = String::from_utf8(trained.stderr).unwrap();
+    assert!(stderr.starts_with("error: --input can't be combined w/ --dir, --lines or --jsonl"), "{}", stderr);
+}
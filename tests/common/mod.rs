@@ -0,0 +1,41 @@
+// helpers shared by the integration tests, pulled in w/ `mod common;`... every test file
+// uses some of them, not all
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::PathBuf;
+
+use tokenizer::observer::NoObserver;
+use tokenizer::training::Verbosity;
+use tokenizer::{BpeTokenizer, TrainerConfig};
+
+/// the English text of the minbpe tests
+pub const TAYLOR_SWIFT: &str = include_str!("../../karpathy_tokenizer/tests/taylorswift.txt");
+
+/// a scratch directory under the system temp dir, unique per test and run
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("tokenizer_{}_{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// panics at the first chunk that differs, the whole lists are too long to print
+pub fn assert_same_chunks(label: &str, expected: &[impl AsRef<str>], chunks: &[impl AsRef<str>]) {
+    if let Some(i) = expected.iter().zip(chunks).position(|(a, b)| a.as_ref() != b.as_ref()) {
+        panic!("{}: chunk {} differs: {:?} vs {:?}", label, i, expected[i].as_ref(), chunks[i].as_ref());
+    }
+    assert_eq!(expected.len(), chunks.len(), "{}", label);
+}
+
+/// trained on the repo's training text w/ the default config and 'vocab_size'
+pub fn trained(vocab_size: u32) -> BpeTokenizer {
+    trained_with(&TrainerConfig { vocab_size, verbosity: Verbosity::Quiet, ..TrainerConfig::default() })
+}
+
+/// trained on the repo's training text w/ 'config'
+pub fn trained_with(config: &TrainerConfig) -> BpeTokenizer {
+    let mut tokenizer = BpeTokenizer::new();
+    let text = fs::read_to_string("data/train_text.txt").unwrap();
+    tokenizer.train_with_config(&text, config, &mut NoObserver).unwrap();
+    tokenizer
+}
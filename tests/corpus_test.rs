@@ -0,0 +1,156 @@
+mod common;
+
+use std::collections::HashMap;
+use std::fs;
+
+use common::{temp_dir, TAYLOR_SWIFT};
+use tokenizer::corpus::{self, BLOCK_LEN};
+use tokenizer::observer::NoObserver;
+use tokenizer::training::{self, Verbosity};
+use tokenizer::{pretokenizer, BpeTokenizer, Corpus, TokenizerError, TrainerConfig};
+
+
+/// the chunk counts of each document added up
+fn counts_of(documents: &[&str], config: &TrainerConfig) -> HashMap<String, u64> {
+    let mut chunk_counts = HashMap::new();
+    for document in documents {
+        training::add_chunk_counts(&mut chunk_counts, document, config);
+    }
+    chunk_counts
+}
+
+
+#[test]
+fn long_files_are_read_in_blocks_w_the_same_chunks() {
+    let dir = temp_dir("blocks");
+    // several blocks, w/ cuts landing in front of lines of all kinds
+    let long = format!("{}\n  indented\n\n\n123 numbers\n!? symbols\r\nend", TAYLOR_SWIFT).repeat(2 * BLOCK_LEN / TAYLOR_SWIFT.len() + 1);
+    let path = dir.join("long.txt");
+    fs::write(&path, &long).unwrap();
+
+    for preset in ["gpt4", "gpt2", "o200k"] {
+        let config = TrainerConfig { pre_tokenizer: pretokenizer::preset(preset).unwrap(), ..TrainerConfig::default() };
+        let counts = corpus::count_corpus_chunks(&Corpus::Files(vec![path.clone()]), &config).unwrap();
+        assert_eq!(counts, training::count_chunks(&long, &config), "{}", preset);
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
+
+
+#[test]
+fn blocks_are_not_cut_after_a_blank_line() {
+    let dir = temp_dir("blank_lines");
+    // the first line past BLOCK_LEN follows "\n\n", which GPT-2 splits as "\n" + "\n" mid-text
+    let long = "a paragraph\n\n".repeat(BLOCK_LEN / 13 + 1) + &"a line\n".repeat(100);
+    let path = dir.join("long.txt");
+    fs::write(&path, &long).unwrap();
+
+    for preset in ["gpt2", "gpt4", "llama3"] {
+        let config = TrainerConfig { pre_tokenizer: pretokenizer::preset(preset).unwrap(), ..TrainerConfig::default() };
+        let counts = corpus::count_corpus_chunks(&Corpus::Files(vec![path.clone()]), &config).unwrap();
+        assert_eq!(counts, training::count_chunks(&long, &config), "{}", preset);
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
+
+
+#[test]
+fn merges_never_cross_documents() {
+    let dir = temp_dir("documents");
+    fs::write(dir.join("one.txt"), "xxxxab").unwrap();
+    fs::write(dir.join("two.txt"), "ba yyyy").unwrap();
+    let config = TrainerConfig {
+        vocab_size: 300,
+        verbosity: Verbosity::Quiet,
+        pre_tokenizer: pretokenizer::none(),
+        ..TrainerConfig::default()
+    };
+    let files = Corpus::Files(vec![dir.join("one.txt"), dir.join("two.txt")]);
+    let counts = corpus::count_corpus_chunks(&files, &config).unwrap();
+    assert_eq!(counts, counts_of(&["xxxxab", "ba yyyy"], &config));
+
+    // "bb" would only come from the end of one file and the start of the next
+    let mut tokenizer = BpeTokenizer::new();
+    tokenizer.train_from_corpus(&files, &config, &mut NoObserver).unwrap();
+    assert!(tokenizer.vocab.vocab_ranks.contains_key(&(120, 120)));
+    assert!(!tokenizer.vocab.vocab_ranks.contains_key(&(98, 98)));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+
+#[test]
+fn directories_are_walked() {
+    let dir = temp_dir("walk");
+    fs::create_dir_all(dir.join("nested/deeper")).unwrap();
+    fs::write(dir.join("a.txt"), "first document\n").unwrap();
+    fs::write(dir.join("nested/b.txt"), "second one").unwrap();
+    fs::write(dir.join("nested/deeper/c.txt"), "and the third's").unwrap();
+    let config = TrainerConfig::default();
+
+    let counts = corpus::count_corpus_chunks(&Corpus::Directory(dir.clone()), &config).unwrap();
+    assert_eq!(counts, counts_of(&["first document\n", "second one", "and the third's"], &config));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+
+#[cfg(unix)]
+#[test]
+fn symlinks_are_not_followed() {
+    let dir = temp_dir("symlinks");
+    let outside = temp_dir("symlinks_outside");
+    fs::create_dir_all(dir.join("nested")).unwrap();
+    fs::write(dir.join("nested/a.txt"), "inside").unwrap();
+    fs::write(outside.join("b.txt"), "outside").unwrap();
+    // a cycle back up the tree, a directory and a file elsewhere
+    std::os::unix::fs::symlink(&dir, dir.join("nested/loop")).unwrap();
+    std::os::unix::fs::symlink(&outside, dir.join("elsewhere")).unwrap();
+    std::os::unix::fs::symlink(outside.join("b.txt"), dir.join("b.txt")).unwrap();
+    let config = TrainerConfig::default();
+
+    let counts = corpus::count_corpus_chunks(&Corpus::Directory(dir.clone()), &config).unwrap();
+    assert_eq!(counts, counts_of(&["inside"], &config));
+    fs::remove_dir_all(dir).unwrap();
+    fs::remove_dir_all(outside).unwrap();
+}
+
+
+#[test]
+fn lines_and_jsonl_are_documents() {
+    let dir = temp_dir("lines");
+    let config = TrainerConfig::default();
+    let documents = ["hello world", "  indented line", "numbers 12345"];
+
+    fs::write(dir.join("docs.txt"), format!("{}\n{}\r\n\n{}", documents[0], documents[1], documents[2])).unwrap();
+    let counts = corpus::count_corpus_chunks(&Corpus::Lines(dir.join("docs.txt")), &config).unwrap();
+    assert_eq!(counts, counts_of(&documents, &config));
+
+    // w/ escapes and other fields around the text
+    let jsonl = "{\"id\": 1, \"body\": \"hello world\"}\n\n{\"body\": \"  indented line\", \"x\": [1]}\n{\"body\": \"numbers 12345\"}\n";
+    fs::write(dir.join("docs.jsonl"), jsonl).unwrap();
+    let corpus = Corpus::Jsonl { path: dir.join("docs.jsonl"), text_field: "body".to_string() };
+    assert_eq!(corpus::count_corpus_chunks(&corpus, &config).unwrap(), counts_of(&documents, &config));
+
+    fs::write(dir.join("escaped.jsonl"), "{\"text\": \"line\\nbreak \\u00e9\"}\n").unwrap();
+    let corpus = Corpus::Jsonl { path: dir.join("escaped.jsonl"), text_field: "text".to_string() };
+    assert_eq!(corpus::count_corpus_chunks(&corpus, &config).unwrap(), counts_of(&["line\nbreak é"], &config));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+
+#[test]
+fn bad_jsonl_lines_are_reported() {
+    let dir = temp_dir("bad_jsonl");
+    let config = TrainerConfig::default();
+    for (name, contents) in [("syntax", "{\"text\": \"ok\"}\n{\"text\": \n"), ("missing", "{\"body\": \"x\"}\n"), ("number", "{\"text\": 5}\n")] {
+        let path = dir.join(format!("{}.jsonl", name));
+        fs::write(&path, contents).unwrap();
+        let corpus = Corpus::Jsonl { path, text_field: "text".to_string() };
+        match corpus::count_corpus_chunks(&corpus, &config) {
+            Err(TokenizerError::InvalidCorpus(message)) => assert!(message.contains("line"), "{}", message),
+            other => panic!("{}: {:?}", name, other),
+        }
+    }
+    let missing = Corpus::Files(vec![dir.join("not_there.txt")]);
+    assert!(matches!(corpus::count_corpus_chunks(&missing, &config), Err(TokenizerError::Io(_))));
+    fs::remove_dir_all(dir).unwrap();
+}
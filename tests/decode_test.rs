@@ -0,0 +1,58 @@
+use tokenizer::{BpeTokenizer, Tokenizer, TokenizerError};
+
+/// a byte level tokenizer (no merges) w/ one special token
+fn tokenizer() -> BpeTokenizer {
+    let mut tokenizer = BpeTokenizer::new();
+    tokenizer.vocab.register_special_tokens(&[("<|endoftext|>", 300)]).unwrap();
+    tokenizer
+}
+
+
+#[test]
+fn bytes_are_exact() {
+    let tokenizer = tokenizer();
+    let ids = vec![104, 105, 0xC3, 300];
+    let mut expected = b"hi\xC3".to_vec();
+    expected.extend_from_slice(b"<|endoftext|>");
+    assert_eq!(tokenizer.vocab.decode_bytes(&ids).unwrap(), expected);
+}
+
+
+#[test]
+fn unknown_ids_are_reported_not_truncated() {
+    let tokenizer = tokenizer();
+    // 360 would have become byte 104 ('h') if truncated to u8
+    let ids = [104, 360, 105];
+    let error = tokenizer.vocab.decode_bytes(&ids).unwrap_err();
+    assert!(matches!(error, TokenizerError::UnknownToken { id: 360, position: 1 }));
+    assert_eq!(tokenizer.vocab.decode_strict(&ids).unwrap_err().to_string(),
+        "unknown token id 360 at position 1");
+    assert_eq!(tokenizer.decode(&ids), "h\u{FFFD}i");
+}
+
+
+#[test]
+fn strict_names_the_token_w_invalid_utf8() {
+    let tokenizer = tokenizer();
+    // "a" then a lone continuation byte
+    let ids = [97, 0xA9, 98];
+    let error = tokenizer.vocab.decode_strict(&ids).unwrap_err();
+    assert!(matches!(error, TokenizerError::InvalidUtf8 { id: 0xA9, position: 1, offset: 1 }));
+    assert_eq!(tokenizer.vocab.decode_lossy(&ids), "a\u{FFFD}b");
+    assert_eq!(tokenizer.vocab.decode_strict(&[0xC3, 0xA9]).unwrap(), "é");
+}
+
+
+#[test]
+fn table_holds_the_bytes_of_every_merge() {
+    let mut tokenizer = BpeTokenizer::new();
+    tokenizer.train(&"hello hello world, hello there ".repeat(10), 300, false).unwrap();
+    let vocab = &tokenizer.vocab;
+    for &((left, right), id) in &vocab.vocab_vec {
+        let mut expected = vocab.token_bytes(left).unwrap().to_vec();
+        expected.extend_from_slice(vocab.token_bytes(right).unwrap());
+        assert_eq!(vocab.token_bytes(id), Some(&expected[..]));
+    }
+    assert_eq!(vocab.token_bytes(b'h' as u32), Some(&b"h"[..]));
+    assert_eq!(vocab.token_bytes(300), None);
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use std::fs;
+
+use tokenizer::pretokenizer::GPT4_SPLIT_PATTERN;
+use tokenizer::{BpeTokenizer, Tokenizer, Vocabulary};
+
+/// "er", " t", "he", " the", "ere" and " the " as a small hand-built model w/ the GPT-4
+/// split... " the " can only form when the text is not split into words
+fn tokenizer() -> BpeTokenizer {
+    let model_file = common::temp_dir("encode").join("tokenizer.model");
+    let model = format!("minbpe v1\n{}\n0\n101 114\n32 116\n104 101\n257 258\n256 101\n259 32\n", GPT4_SPLIT_PATTERN);
+    fs::write(&model_file, model).unwrap();
+    let vocab = Vocabulary::load(&model_file).unwrap();
+    fs::remove_file(&model_file).unwrap();
+    BpeTokenizer::from_vocabulary(vocab)
+}
+
+
+#[test]
+fn merges_a_pair_that_occurs_once() {
+    let tokenizer = tokenizer();
+    assert_eq!(tokenizer.encode(" the").unwrap(), vec![259]);
+    assert_eq!(tokenizer.encode("ere").unwrap(), vec![260]);
+}
+
+
+#[test]
+fn lowest_rank_pair_wins() {
+    let tokenizer = tokenizer();
+    // " there": "er" (256) is merged before " t" (257) so "he" (258) never forms
+    assert_eq!(tokenizer.encode(" there").unwrap(), vec![257, 104, 260]);
+}
+
+
+#[test]
+fn chunks_encode_independently_of_context() {
+    let tokenizer = tokenizer();
+    let alone = tokenizer.encode(" the").unwrap();
+    let in_text = tokenizer.encode("over the other there").unwrap();
+    // "over" -> [o, v, er] comes before " the", which does not merge w/ the space after it
+    assert_eq!(&in_text[3..5], &[alone[0], 32]);
+    assert_eq!(tokenizer.decode(&in_text), "over the other there");
+}
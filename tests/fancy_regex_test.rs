@@ -0,0 +1,49 @@
+// differential tests between the default regex crate path and the patterns run verbatim
+#![cfg(feature = "fancy-regex")]
+
+mod common;
+
+use common::TAYLOR_SWIFT;
+use tokenizer::pretokenizer::{self, FancyRegexPreTokenizer, PreTokenizer};
+
+/// ~200k characters drawn from pieces that stress the patterns: contractions in both
+/// cases, runs of mixed whitespace and newlines, CJK, emoji, combining marks, digits
+fn unicode_corpus() -> String {
+    let pieces = [
+        "a", "Z", "é", "'s", "'S", "'ll", "'VE", "'t", "T", " ", " ", "  ", "\t", "\n", "\r\n",
+        "\u{a0}", "\u{3000}", "東京", "🦀", "👩‍💻", "1", "2345", ".", ",", "!", "-", "/", "\u{301}",
+        "ǅ", "Ǆ", "ß", "Ωμέγα", "Привет", "مرحبا", "\u{200b}", "\u{feff}", "x",
+    ];
+    // a small LCG so the corpus is the same on every run
+    let mut state: u64 = 0x2545F4914F6CDD1D;
+    let mut corpus = String::new();
+    while corpus.chars().count() < 200_000 {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        corpus.push_str(pieces[(state >> 33) as usize % pieces.len()]);
+    }
+    corpus
+}
+
+
+fn assert_same_chunks(name: &str, text: &str) {
+    let default = pretokenizer::preset(name).unwrap();
+    let verbatim = FancyRegexPreTokenizer::new(default.pattern()).unwrap();
+    common::assert_same_chunks(name, &verbatim.split(text), &default.split(text));
+}
+
+
+#[test]
+fn presets_match_verbatim_patterns_on_english() {
+    for name in ["gpt2", "gpt4", "o200k", "llama3"] {
+        assert_same_chunks(name, TAYLOR_SWIFT);
+    }
+}
+
+
+#[test]
+fn presets_match_verbatim_patterns_on_unicode() {
+    let corpus = unicode_corpus();
+    for name in ["gpt2", "gpt4", "o200k", "llama3"] {
+        assert_same_chunks(name, &corpus);
+    }
+}
@@ -0,0 +1,87 @@
+"""
+Builds the small tiktoken-style rank file used by tests/tiktoken_test.rs.
+
+A few hundred merges are trained minbpe-style on data/train_text.txt, the raw
+bytes are given the same permuted ranks as cl100k_base (GPT-2's printable bytes
+first), and the expected ids are produced with tiktoken's own rank-based
+byte pair merge so the Rust side can be checked without downloading anything.
+
+run from the repository root:
+    python3 tests/fixtures/make_tiktoken_fixture.py
+"""
+import base64
+import regex as re
+
+GPT4_SPLIT_PATTERN = r"""'(?i:[sdmt]|ll|ve|re)|[^\r\n\p{L}\p{N}]?+\p{L}+|\p{N}{1,3}| ?[^\s\p{L}\p{N}]++[\r\n]*|\s*[\r\n]|\s+(?!\S)|\s+"""
+NUM_MERGES = 300
+
+
+def byte_ranks():
+    # the historical byte order used by GPT-2 and kept by cl100k_base
+    bs = list(range(ord("!"), ord("~") + 1)) + list(range(ord("¡"), ord("¬") + 1)) + list(range(ord("®"), ord("ÿ") + 1))
+    bs += [b for b in range(256) if b not in bs]
+    return {bytes([b]): rank for rank, b in enumerate(bs)}
+
+
+def train(text):
+    ids = [list(ch.encode("utf-8")) for ch in re.findall(GPT4_SPLIT_PATTERN, text)]
+    vocab = {idx: bytes([idx]) for idx in range(256)}
+    tokens = []
+    for i in range(NUM_MERGES):
+        stats = {}
+        for chunk in ids:
+            for pair in zip(chunk, chunk[1:]):
+                stats[pair] = stats.get(pair, 0) + 1
+        pair = max(stats.items(), key=lambda x: (x[1], -x[0][0], -x[0][1]))[0]
+        idx = 256 + i
+        vocab[idx] = vocab[pair[0]] + vocab[pair[1]]
+        tokens.append(vocab[idx])
+        new_ids = []
+        for chunk in ids:
+            out, j = [], 0
+            while j < len(chunk):
+                if j < len(chunk) - 1 and (chunk[j], chunk[j + 1]) == pair:
+                    out.append(idx)
+                    j += 2
+                else:
+                    out.append(chunk[j])
+                    j += 1
+            new_ids.append(out)
+        ids = new_ids
+    return tokens
+
+
+def tiktoken_encode(ranks, text):
+    # mirrors tiktoken's _byte_pair_merge on every regex chunk
+    ids = []
+    for chunk in re.findall(GPT4_SPLIT_PATTERN, text):
+        piece = chunk.encode("utf-8")
+        if piece in ranks:
+            ids.append(ranks[piece])
+            continue
+        parts = [bytes([b]) for b in piece]
+        while True:
+            best = None
+            for i in range(len(parts) - 1):
+                rank = ranks.get(parts[i] + parts[i + 1])
+                if rank is not None and (best is None or rank < best[0]):
+                    best = (rank, i)
+            if best is None:
+                break
+            i = best[1]
+            parts = parts[:i] + [parts[i] + parts[i + 1]] + parts[i + 2:]
+        ids.extend(ranks[p] for p in parts)
+    return ids
+
+
+if __name__ == "__main__":
+    text = open("data/train_text.txt", encoding="utf-8").read()
+    ranks = byte_ranks()
+    for token in train(text):
+        ranks[token] = len(ranks)
+    with open("tests/fixtures/mini.tiktoken", "w") as f:
+        for token, rank in sorted(ranks.items(), key=lambda x: x[1]):
+            f.write(f"{base64.b64encode(token).decode()} {rank}\n")
+    sample = open("tests/fixtures/tiktoken_sample.txt", encoding="utf-8").read()
+    with open("tests/fixtures/tiktoken_sample_ids.txt", "w") as f:
+        f.write(" ".join(str(i) for i in tiktoken_encode(ranks, sample)) + "\n")
@@ -0,0 +1,101 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{trained, TAYLOR_SWIFT};
+use tokenizer::pretokenizer;
+use tokenizer::training;
+use tokenizer::{BpeTokenizer, Tokenizer, Vocabulary};
+
+/// far more than the linear encoder needs for a megabyte even unoptimized... the
+/// rescanning encoder takes minutes on these inputs
+const LIMIT: Duration = Duration::from_secs(20);
+
+
+/// the rescanning encoder: merge every occurrence of the lowest ranked pair, again and again
+fn reference(vocab: &Vocabulary, chunk: &[u8]) -> Vec<u32> {
+    let mut ids: Vec<u32> = chunk.iter().map(|&b| vocab.byte_token(b)).collect();
+    while let Some((new_word, pair)) = ids
+        .windows(2)
+        .filter_map(|window| vocab.vocab_ranks.get(&(window[0], window[1])).map(|&rank| (rank, (window[0], window[1]))))
+        .min()
+    {
+        ids = training::merge(&ids, pair, new_word);
+    }
+    ids
+}
+
+/// 'len' bytes of printable ASCII from a fixed LCG
+fn noise(len: usize) -> String {
+    let mut state: u64 = 42;
+    (0..len)
+        .map(|_| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let byte = (state >> 33) as u8 % 96;
+            if byte == 95 { '\n' } else { (b' ' + byte) as char }
+        })
+        .collect()
+}
+
+
+#[test]
+fn same_ids_as_rescanning() {
+    let tokenizer = trained(600);
+    // every chunk of the text, mostly below the length where encode_chunk() switches
+    for chunk in pretokenizer::gpt4().chunks(TAYLOR_SWIFT) {
+        assert_eq!(tokenizer.encode_chunk_linear(chunk.as_bytes()), tokenizer.encode_chunk(chunk.as_bytes(), false));
+    }
+    // long chunks of text and of noise
+    let noisy = noise(5000);
+    for long in [&TAYLOR_SWIFT[..20_000], &TAYLOR_SWIFT[100_000..104_000], &noisy[..]] {
+        assert_eq!(tokenizer.encode_chunk_linear(long.as_bytes()), reference(&tokenizer.vocab, long.as_bytes()));
+    }
+}
+
+
+#[test]
+fn overlapping_pairs_merge_left_to_right() {
+    let mut vocab = Vocabulary::new();
+    vocab.add_merge((97, 97), 256);     // "aa"
+    vocab.add_merge((256, 97), 257);    // "aaa"
+    vocab.add_merge((98, 97), 258);     // "ba"
+    let tokenizer = BpeTokenizer::from_vocabulary(vocab);
+    for chunk in ["aaa", "aaaa", "aaaaa", "baaa", "abaaba", "aabaaaaabaa"] {
+        assert_eq!(tokenizer.encode_chunk_linear(chunk.as_bytes()), reference(&tokenizer.vocab, chunk.as_bytes()), "{}", chunk);
+    }
+    assert_eq!(tokenizer.encode_chunk_linear(b"aaaaa"), vec![256, 257]);
+}
+
+
+#[test]
+fn megabyte_run_of_one_character() {
+    // merges doubling a run of "a" up to 1024 bytes, then 2^20 of them in one chunk
+    let mut vocab = Vocabulary::new();
+    vocab.pre_tokenizer = pretokenizer::none();
+    let mut token = 97;
+    for new_word in 256..266 {
+        vocab.add_merge((token, token), new_word);
+        token = new_word;
+    }
+    let tokenizer = BpeTokenizer::from_vocabulary(vocab);
+    let text = "a".repeat(1 << 20);
+
+    let start = Instant::now();
+    let ids = tokenizer.encode(&text).unwrap();
+    assert!(start.elapsed() < LIMIT, "took {:?}", start.elapsed());
+    assert_eq!(ids, vec![265; 1024]);
+}
+
+
+#[test]
+fn megabyte_chunk_of_text() {
+    let mut tokenizer = trained(600);
+    tokenizer.vocab.pre_tokenizer = pretokenizer::none();
+    for long in [TAYLOR_SWIFT.repeat(6), noise(1 << 20)] {
+        let start = Instant::now();
+        let ids = tokenizer.encode(&long).unwrap();
+        assert!(start.elapsed() < LIMIT, "took {:?}", start.elapsed());
+        assert!(ids.len() < long.len());
+        assert_eq!(tokenizer.decode(&ids), long);
+    }
+}
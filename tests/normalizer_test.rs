@@ -0,0 +1,94 @@
+mod common;
+
+use std::borrow::Cow;
+use std::fs;
+
+use tokenizer::observer::NoObserver;
+use tokenizer::training::{self, Verbosity};
+use tokenizer::{BpeTokenizer, Normalization, Normalizer, Tokenizer, TrainerConfig, Vocabulary};
+
+
+#[test]
+fn steps_normalize_the_text() {
+    let nfc = Normalizer::new(vec![Normalization::Nfc]);
+    assert_eq!(nfc.normalize("cafe\u{301}"), "caf\u{e9}");
+    // compatibility forms are only folded by NFKC
+    assert_eq!(nfc.normalize("\u{fb01}le"), "\u{fb01}le");
+    assert_eq!(Normalizer::new(vec![Normalization::Nfkc]).normalize("\u{fb01}le \u{ff11}"), "file 1");
+
+    let strip = Normalizer::new(vec![Normalization::StripControl]);
+    assert_eq!(strip.normalize("a\u{0}b\u{7}c\td\r\n"), "abc\td\r\n");
+
+    let newlines = Normalizer::new(vec![Normalization::Newlines]);
+    assert_eq!(newlines.normalize("one\r\ntwo\rthree\n"), "one\ntwo\rthree\n");
+}
+
+
+#[test]
+fn normalized_text_is_borrowed() {
+    let all = Normalizer::new(vec![
+        Normalization::Nfc, Normalization::Nfkc, Normalization::StripControl, Normalization::Newlines,
+    ]);
+    assert!(matches!(all.normalize("plain text\n"), Cow::Borrowed(_)));
+    assert!(matches!(Normalizer::default().normalize("cafe\u{301}\r\n"), Cow::Borrowed(_)));
+    assert!(matches!(all.normalize("cafe\u{301}"), Cow::Owned(_)));
+}
+
+
+#[test]
+fn steps_parse_and_print() {
+    let normalizer = Normalizer::parse("nfc, strip-control,newlines").unwrap();
+    assert_eq!(normalizer.steps(), [Normalization::Nfc, Normalization::StripControl, Normalization::Newlines]);
+    assert_eq!(normalizer.to_string(), "nfc,strip-control,newlines");
+    assert!(Normalizer::parse("").unwrap().is_empty());
+    assert!(Normalizer::parse("nfd").is_err());
+}
+
+
+#[test]
+fn training_and_encoding_see_the_same_text() {
+    // the same words w/ composed and decomposed accents count as one chunk
+    let composed = "caf\u{e9} caf\u{e9} caf\u{e9} ";
+    let decomposed = "cafe\u{301} cafe\u{301} cafe\u{301} ";
+    let config = TrainerConfig {
+        vocab_size: 262,
+        verbosity: Verbosity::Quiet,
+        normalizer: Normalizer::new(vec![Normalization::Nfc]),
+        ..TrainerConfig::default()
+    };
+    let text = format!("{}{}", composed, decomposed);
+    let chunk_counts = training::count_chunks(&text, &config);
+    assert_eq!(chunk_counts.get(" caf\u{e9}"), Some(&5));
+
+    let mut tokenizer = BpeTokenizer::new();
+    tokenizer.train_with_config(&text, &config, &mut NoObserver).unwrap();
+    assert_eq!(tokenizer.vocab.normalizer, config.normalizer);
+    assert_eq!(tokenizer.encode(composed).unwrap(), tokenizer.encode(decomposed).unwrap());
+    assert_eq!(tokenizer.encode(" cafe\u{301}").unwrap().len(), 1);
+}
+
+
+#[test]
+fn normalizer_is_saved_with_the_model() {
+    let prefix = common::temp_dir("normalized")
+        .join("tokenizer")
+        .to_string_lossy()
+        .into_owned();
+    let mut vocab = Vocabulary::new();
+    vocab.normalizer = Normalizer::new(vec![Normalization::Nfkc, Normalization::Newlines]);
+    vocab.add_merge((13, 10), 256);
+    vocab.save(&prefix).unwrap();
+
+    let model = fs::read_to_string(format!("{}.model", prefix)).unwrap();
+    assert!(model.starts_with("minbpe v1 normalized\nnfkc,newlines\n"));
+    let loaded = BpeTokenizer::from_vocabulary(Vocabulary::load(format!("{}.model", prefix)).unwrap());
+    assert_eq!(loaded.vocab.normalizer, vocab.normalizer);
+    // "\r\n" is a newline by the time the merge could apply
+    assert_eq!(loaded.encode("a\r\nb").unwrap(), vec![97, 10, 98]);
+
+    // a model w/o normalization stays readable by minbpe
+    Vocabulary::new().save(&prefix).unwrap();
+    assert!(fs::read_to_string(format!("{}.model", prefix)).unwrap().starts_with("minbpe v1\n"));
+    fs::remove_file(format!("{}.model", prefix)).unwrap();
+    fs::remove_file(format!("{}.vocab", prefix)).unwrap();
+}
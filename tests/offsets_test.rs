@@ -0,0 +1,75 @@
+use tokenizer::{BpeTokenizer, Normalization, Normalizer, Tokenizer, TokenSpan, TokenizerError, Vocabulary};
+
+
+/// a tokenizer w/ the given merges on top of the raw bytes
+fn with_merges(merges: &[(u32, u32)]) -> BpeTokenizer {
+    let mut vocab = Vocabulary::new();
+    for (i, &pair) in merges.iter().enumerate() {
+        vocab.add_merge(pair, 256 + i as u32);
+    }
+    BpeTokenizer::from_vocabulary(vocab)
+}
+
+
+#[test]
+fn spans_cover_the_text_in_order() {
+    let mut tokenizer = BpeTokenizer::new();
+    let text = std::fs::read_to_string("data/train_text.txt").unwrap();
+    tokenizer.train(&text, 400, false).unwrap();
+
+    let sample = "The café's 12345 naïve 🦀 tokens\r\n  end";
+    let spans = tokenizer.encode_with_offsets(sample).unwrap();
+    let ids: Vec<u32> = spans.iter().map(|span| span.id).collect();
+    assert_eq!(ids, tokenizer.encode(sample).unwrap());
+
+    let mut end = 0;
+    for span in &spans {
+        assert_eq!(span.bytes.start, end);
+        assert_eq!(&sample.as_bytes()[span.bytes.clone()], tokenizer.vocab.token_bytes(span.id).unwrap());
+        end = span.bytes.end;
+    }
+    assert_eq!(end, sample.len());
+    assert_eq!(spans.last().unwrap().chars.end, sample.chars().count());
+}
+
+
+#[test]
+fn char_ranges_count_characters() {
+    // "he" merged, everything else raw bytes
+    let tokenizer = with_merges(&[(104, 101)]);
+    let spans = tokenizer.encode_with_offsets("é he").unwrap();
+    assert_eq!(spans, vec![
+        TokenSpan { id: 0xC3, bytes: 0..1, chars: 0..1 },
+        TokenSpan { id: 0xA9, bytes: 1..2, chars: 0..1 },
+        TokenSpan { id: 32, bytes: 2..3, chars: 1..2 },
+        TokenSpan { id: 256, bytes: 3..5, chars: 2..4 },
+    ]);
+}
+
+
+#[test]
+fn tokens_ending_inside_a_character() {
+    // " \xC3" ends in the middle of "é"... both it and the rest of "é" cover that char
+    let tokenizer = with_merges(&[(32, 0xC3)]);
+    let spans = tokenizer.encode_with_offsets("a é!").unwrap();
+    assert_eq!(spans, vec![
+        TokenSpan { id: 97, bytes: 0..1, chars: 0..1 },
+        TokenSpan { id: 256, bytes: 1..3, chars: 1..3 },
+        TokenSpan { id: 0xA9, bytes: 3..4, chars: 2..3 },
+        TokenSpan { id: 33, bytes: 4..5, chars: 3..4 },
+    ]);
+}
+
+
+#[test]
+fn normalized_text_has_no_offsets() {
+    let mut tokenizer = with_merges(&[]);
+    tokenizer.vocab.normalizer = Normalizer::new(vec![Normalization::Newlines]);
+    assert_eq!(tokenizer.encode_with_offsets("a\nb").unwrap().len(), 3);
+    assert!(matches!(tokenizer.encode_with_offsets("a\r\nb"), Err(TokenizerError::Unsupported(_))));
+
+    // U+0301 after "x" has no composed form: NFC has to look but leaves it as it is
+    tokenizer.vocab.normalizer = Normalizer::new(vec![Normalization::Nfc]);
+    assert_eq!(tokenizer.encode_with_offsets("x\u{301}").unwrap().len(), 3);
+    assert!(matches!(tokenizer.encode_with_offsets("e\u{301}"), Err(TokenizerError::Unsupported(_))));
+}
@@ -0,0 +1,74 @@
+#![cfg(feature = "parallel")]
+
+mod common;
+
+use std::fs;
+
+use common::TAYLOR_SWIFT;
+use tokenizer::{pretokenizer, BpeTokenizer, Normalization, Normalizer, Tokenizer, TrainerConfig};
+use tokenizer::observer::NoObserver;
+use tokenizer::training::Verbosity;
+
+
+/// common::trained(600) w/ NFC and the given pre-tokenizer preset
+fn trained(preset: &str) -> BpeTokenizer {
+    common::trained_with(&TrainerConfig {
+        vocab_size: 600,
+        verbosity: Verbosity::Quiet,
+        normalizer: Normalizer::new(vec![Normalization::Nfc]),
+        pre_tokenizer: pretokenizer::preset(preset).unwrap(),
+        ..TrainerConfig::default()
+    })
+}
+
+
+#[test]
+fn parallel_encode_matches_serial() {
+    let mixed = "Ωμέγα Привет, 東京 🦀 naïve cafe\u{301} — it's 2024!\r\n".repeat(500);
+    for preset in ["gpt4", "gpt2", "o200k"] {
+        let tokenizer = trained(preset);
+        for sample in [TAYLOR_SWIFT, &mixed[..], "", "a"] {
+            assert_eq!(tokenizer.encode_parallel(sample).unwrap(), tokenizer.encode(sample).unwrap(), "{}", preset);
+        }
+    }
+}
+
+
+#[test]
+fn batch_keeps_document_order() {
+    let tokenizer = trained("gpt4");
+    let text = fs::read_to_string("data/encode_text.txt").unwrap();
+    let documents: Vec<&str> = text.lines().collect();
+    let batch = tokenizer.encode_batch(&documents).unwrap();
+    assert_eq!(batch.len(), documents.len());
+    for (ids, document) in batch.iter().zip(&documents) {
+        assert_eq!(ids, &tokenizer.encode(document).unwrap());
+    }
+}
+
+
+#[test]
+fn same_ids_on_any_number_of_threads() {
+    let tokenizer = trained("gpt4");
+    let serial = tokenizer.encode(TAYLOR_SWIFT).unwrap();
+    for threads in [1, 3, 8] {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+        assert_eq!(pool.install(|| tokenizer.encode_parallel(TAYLOR_SWIFT)).unwrap(), serial);
+    }
+}
+
+
+#[test]
+fn training_is_the_same_on_any_number_of_threads() {
+    // enough distinct chunks for the counting and merging to be sharded
+    let config = TrainerConfig { vocab_size: 400, verbosity: Verbosity::Quiet, ..TrainerConfig::default() };
+    let train = |threads: usize| {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+        pool.install(|| tokenizer::training::train_tokenizer(TAYLOR_SWIFT, &config, &mut NoObserver).unwrap())
+    };
+    let single = train(1);
+    assert_eq!(single.vocab_vec.len(), 144, "every merge learned");
+    for threads in [3, 8] {
+        assert_eq!(train(threads).vocab_vec, single.vocab_vec, "{} threads", threads);
+    }
+}
@@ -0,0 +1,82 @@
+use std::fs;
+
+use tokenizer::{BpeTokenizer, Tokenizer, TokenizerError, Vocabulary};
+
+/// a scratch file prefix under the system temp dir, unique per test
+fn temp_prefix(name: &str) -> String {
+    let mut path = std::env::temp_dir();
+    path.push(format!("tokenizer_{}_{}", name, std::process::id()));
+    path.to_string_lossy().into_owned()
+}
+
+
+#[test]
+fn save_then_load_keeps_merges_and_encoding() {
+    let prefix = temp_prefix("roundtrip");
+    // "er", "20", " t", " the" as the first merges learned from train_text.txt
+    fs::write(format!("{}.model", prefix), "minbpe v1\n\n0\n101 114\n50 48\n32 116\n104 101\n258 259\n").unwrap();
+    let trained = BpeTokenizer::from_vocabulary(Vocabulary::load(format!("{}.model", prefix)).unwrap());
+
+    trained.vocab.save(&prefix).unwrap();
+    let loaded = BpeTokenizer::from_vocabulary(Vocabulary::load(format!("{}.model", prefix)).unwrap());
+
+    assert_eq!(trained.vocab.vocab_vec, loaded.vocab.vocab_vec);
+    assert_eq!(trained.vocab.pre_tokenizer.pattern(), loaded.vocab.pre_tokenizer.pattern());
+    let sample = "there were the other 2020 events";
+    assert_eq!(trained.encode(sample).unwrap(), loaded.encode(sample).unwrap());
+
+    let vocab_file = fs::read_to_string(format!("{}.vocab", prefix)).unwrap();
+    assert!(vocab_file.starts_with("[\\u0000] 0\n"));
+    assert!(vocab_file.contains("[e][r] -> [er] 256\n"));
+    assert!(vocab_file.contains("[ t][he] -> [ the] 260\n"));
+    fs::remove_file(format!("{}.model", prefix)).unwrap();
+    fs::remove_file(format!("{}.vocab", prefix)).unwrap();
+}
+
+
+#[test]
+fn load_reads_minbpe_model_file() {
+    let prefix = temp_prefix("minbpe");
+    let model_file = format!("{}.model", prefix);
+    // as written by minbpe's Tokenizer.save() w/ one special token
+    fs::write(&model_file, "minbpe v1\n\n1\n<|endoftext|> 259\n104 105\n256 33\n").unwrap();
+
+    let vocab = Vocabulary::load(&model_file).unwrap();
+    assert_eq!(vocab.pre_tokenizer.pattern(), "");
+    assert_eq!(vocab.special_tokens, vec![("<|endoftext|>".to_string(), 259)]);
+    assert_eq!(vocab.vocab_vec, vec![((104, 105), 256), ((256, 33), 257)]);
+    // one past the special token, 258 is unused
+    assert_eq!(vocab.len(), 260);
+    fs::remove_file(&model_file).unwrap();
+}
+
+
+#[test]
+fn load_rejects_corrupt_model_file() {
+    let prefix = temp_prefix("corrupt");
+    let model_file = format!("{}.model", prefix);
+    fs::write(&model_file, "minbpe v1\n\n0\n104 300\n").unwrap();
+
+    assert!(matches!(Vocabulary::load(&model_file), Err(TokenizerError::CorruptModel(_))));
+    fs::remove_file(&model_file).unwrap();
+    assert!(matches!(Vocabulary::load(&model_file), Err(TokenizerError::Io(_))));
+}
+
+
+#[test]
+fn load_rejects_conflicting_special_ids() {
+    let prefix = temp_prefix("special_ids");
+    let model_file = format!("{}.model", prefix);
+    // one merge mints id 256, so a special token can't take it
+    for model in [
+        "minbpe v1\n\n1\n<|end|> 256\n104 105\n",
+        "minbpe v1\n\n2\n<|end|> 257\n<|pad|> 257\n104 105\n",
+        "minbpe v1\n\n2\n<|end|> 257\n<|end|> 258\n104 105\n",
+    ] {
+        fs::write(&model_file, model).unwrap();
+        assert!(matches!(Vocabulary::load(&model_file), Err(TokenizerError::CorruptModel(_))), "{:?}", model);
+    }
+    fs::write(&model_file, "minbpe v1\n\n2\n<|end|> 257\n<|pad|> 300\n104 105\n").unwrap();
+    assert_eq!(Vocabulary::load(&model_file).unwrap().len(), 301);
+    fs::remove_file(&model_file).unwrap();
+}
@@ -0,0 +1,102 @@
+use tokenizer::pretokenizer::{self, PreTokenizer, RegexPreTokenizer};
+use tokenizer::TokenizerError;
+
+/// contractions, tabs and no-break spaces before words, long numbers,
+/// newline runs, camel case and trailing spaces
+const TEXT: &str = "Hello  world's\tTABS\t\tend\u{a0}\u{a0}nbsp 12345 DON'T don't\r\n\n  x camelCaseWord ../path/x  ";
+
+// expected chunks are what Python's regex module returns for the original patterns
+
+#[test]
+fn gpt2_matches_python() {
+    assert_eq!(pretokenizer::gpt2().split(TEXT), vec![
+        "Hello", " ", " world", "'s", "\t", "TABS", "\t", "\t", "end", "\u{a0}", "\u{a0}", "nbsp",
+        " 12345", " DON", "'", "T", " don", "'t", "\r\n\n ", " x", " camelCaseWord", " ../", "path",
+        "/", "x", "  ",
+    ]);
+}
+
+
+#[test]
+fn gpt4_matches_python() {
+    assert_eq!(pretokenizer::gpt4().split(TEXT), vec![
+        "Hello", " ", " world", "'s", "\tTABS", "\t", "\tend", "\u{a0}", "\u{a0}nbsp", " ", "123",
+        "45", " DON", "'T", " don", "'t", "\r\n\n", " ", " x", " camelCaseWord", " ../", "path", "/x", "  ",
+    ]);
+    // Llama 3 only differs from it in ways this text does not show
+    assert_eq!(pretokenizer::llama3().split(TEXT), pretokenizer::gpt4().split(TEXT));
+}
+
+
+#[test]
+fn o200k_matches_python() {
+    assert_eq!(pretokenizer::o200k().split(TEXT), vec![
+        "Hello", " ", " world's", "\tTABS", "\t", "\tend", "\u{a0}", "\u{a0}nbsp", " ", "123", "45",
+        " DON'T", " don't", "\r\n\n", " ", " x", " camel", "Case", "Word", " ../", "path", "/x", "  ",
+    ]);
+}
+
+
+#[test]
+fn none_keeps_the_text_whole() {
+    assert_eq!(pretokenizer::none().split(TEXT), vec![TEXT]);
+    assert!(pretokenizer::none().split("").is_empty());
+}
+
+
+#[test]
+fn patterns_round_trip_to_the_presets() {
+    for name in ["gpt2", "gpt4", "o200k", "llama3", "none"] {
+        let preset = pretokenizer::preset(name).unwrap();
+        let loaded = pretokenizer::from_pattern(preset.pattern()).unwrap();
+        assert_eq!(loaded.pattern(), preset.pattern());
+        assert_eq!(loaded.split(TEXT), preset.split(TEXT));
+    }
+    assert!(pretokenizer::preset("gpt5").is_none());
+}
+
+
+#[test]
+fn custom_patterns_compile_or_fail_cleanly() {
+    let words = RegexPreTokenizer::new(r"\w+|\W+").unwrap();
+    assert_eq!(words.split("ab, cd"), vec!["ab", ", ", "cd"]);
+    assert!(matches!(RegexPreTokenizer::new(r"(?<=a)b"), Err(TokenizerError::InvalidPattern(_))));
+}
+
+
+#[test]
+fn only_possessive_quantifiers_are_rewritten() {
+    // an escaped '+' repeated is a run of them, not a single one
+    let escaped = RegexPreTokenizer::new(r"\++|[^+]+").unwrap();
+    assert_eq!(escaped.split("a+++b+c"), vec!["a", "+++", "b", "+", "c"]);
+    // a class holding '?' and '+' keeps both
+    let class = RegexPreTokenizer::new(r"[?+]|[^?+]+").unwrap();
+    assert_eq!(class.split("a?+b"), vec!["a", "?", "+", "b"]);
+    // possessive quantifiers after an atom, a class, a group and a count
+    let possessive = RegexPreTokenizer::new(r"x?+\p{L}++|[0-9]{1,2}+|(?:ab)*+c|\s++").unwrap();
+    assert_eq!(possessive.split("xyz 1234 ababc"), vec!["xyz", " ", "12", "34", " ", "ababc"]);
+    // nothing the regex crate can run the same way
+    for pattern in [r"a+?+", r"a++*", r"a?+?"] {
+        assert!(matches!(RegexPreTokenizer::new(pattern), Err(TokenizerError::InvalidPattern(_))), "{}", pattern);
+    }
+}
+
+
+#[test]
+fn chunks_are_slices_of_the_text() {
+    for name in ["gpt2", "gpt4", "o200k", "llama3", "none"] {
+        let preset = pretokenizer::preset(name).unwrap();
+        let mut chunks = preset.chunks(TEXT);
+        let mut offsets = Vec::new();
+        let mut slices = Vec::new();
+        while let Some(chunk) = chunks.next() {
+            // each slice points into TEXT right where the previous one ended
+            offsets.push(chunk.as_ptr() as usize - TEXT.as_ptr() as usize);
+            slices.push(chunk);
+            assert_eq!(chunks.offset(), offsets.last().unwrap() + chunk.len());
+        }
+        assert_eq!(slices.concat(), TEXT);
+        assert_eq!(slices, preset.split(TEXT));
+        assert_eq!(offsets[0], 0);
+    }
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use common::TAYLOR_SWIFT;
+use tokenizer::pretokenizer::{PreTokenizer, RegexPreTokenizer, GPT4_SPLIT_PATTERN};
+use tokenizer::scanner::{gpt4_chunks, Gpt4Scanner};
+
+fn regex_chunks(text: &str) -> Vec<String> {
+    RegexPreTokenizer::new(GPT4_SPLIT_PATTERN).unwrap().split(text)
+}
+
+fn assert_same_chunks(text: &str) {
+    let chunks: Vec<&str> = gpt4_chunks(text).collect();
+    common::assert_same_chunks("gpt4", &regex_chunks(text), &chunks);
+}
+
+
+#[test]
+fn matches_regex_on_english() {
+    assert_same_chunks(TAYLOR_SWIFT);
+}
+
+
+#[test]
+fn matches_regex_on_edge_cases() {
+    for text in [
+        "", " ", "  ", "a", "'", "'s", "'S", "'ſ", "'LL", "'lL", "'ve", "'re'", "'x", "''s", "it's", "x 's",
+        "123456789", "1a2b", " 12", "  x", "\t\tx", " \u{a0}x", "x  ", "a\r\n\r\nb", " \n \n x", "\n\n  ",
+        "!!", " !!\n\n", "  !!", "\t!", "'!", "\u{a0}!", "...\r\n", "a.b", "😀😀 😀", "東京 東京",
+    ] {
+        assert_same_chunks(text);
+    }
+}
+
+
+#[test]
+fn matches_regex_for_every_character() {
+    // "a" c "1" chunks as ["ac", "1"] for a letter, ["a", "c1"] for a number and
+    // ["a", "c", "1"] for anything else, so a wrong table entry changes the chunks
+    let mut text = String::new();
+    for c in (0..=0x10FFFF).filter_map(char::from_u32) {
+        text.push('a');
+        text.push(c);
+        text.push('1');
+    }
+    assert_same_chunks(&text);
+}
+
+
+#[test]
+fn chunks_borrow_from_the_text() {
+    let text = "Hello world, it's 2024!";
+    let chunks: Vec<&str> = gpt4_chunks(text).collect();
+    assert_eq!(chunks.concat(), text);
+    assert_eq!(Gpt4Scanner.split(text), chunks);
+    // chunks() is a trait method, a concrete pre-tokenizer has it too
+    assert_eq!(Gpt4Scanner.chunks(text).collect::<Vec<_>>(), chunks);
+    assert!(chunks.iter().all(|chunk| text.as_ptr() <= chunk.as_ptr()));
+}
@@ -0,0 +1,68 @@
+use std::collections::HashSet;
+
+use tokenizer::{AllowedSpecial, BpeTokenizer, Tokenizer, TokenizerError};
+
+const TEXT: &str = "<|endoftext|>Hello<|fim_prefix|> world";
+
+/// a byte level tokenizer (no merges) w/ two special tokens
+fn tokenizer() -> BpeTokenizer {
+    let mut tokenizer = BpeTokenizer::new();
+    tokenizer.vocab.register_special_tokens(&[("<|endoftext|>", 1000), ("<|fim_prefix|>", 1001)]).unwrap();
+    tokenizer
+}
+
+fn bytes(text: &str) -> Vec<u32> {
+    text.bytes().map(|b| b as u32).collect()
+}
+
+
+#[test]
+fn all_emits_every_special_token() {
+    let ids = tokenizer().encode_special(TEXT, &AllowedSpecial::All).unwrap();
+    let mut expected = vec![1000];
+    expected.extend(bytes("Hello"));
+    expected.push(1001);
+    expected.extend(bytes(" world"));
+    assert_eq!(ids, expected);
+    assert_eq!(tokenizer().decode(&ids), TEXT);
+}
+
+
+#[test]
+fn none_encodes_special_text_as_ordinary_text() {
+    let tokenizer = tokenizer();
+    let ids = tokenizer.encode_special(TEXT, &AllowedSpecial::None).unwrap();
+    assert_eq!(ids, tokenizer.encode(TEXT).unwrap());
+    assert!(ids.iter().all(|&id| id < 256));
+}
+
+
+#[test]
+fn none_raise_rejects_special_text() {
+    let err = tokenizer().encode_special(TEXT, &AllowedSpecial::NoneRaise).unwrap_err();
+    assert!(matches!(err, TokenizerError::DisallowedSpecialToken(special) if special == "<|endoftext|>"));
+    assert!(tokenizer().encode_special("Hello world", &AllowedSpecial::NoneRaise).is_ok());
+}
+
+
+#[test]
+fn set_only_emits_listed_tokens() {
+    let allowed = AllowedSpecial::Set(HashSet::from(["<|fim_prefix|>"]));
+    let ids = tokenizer().encode_special(TEXT, &allowed).unwrap();
+    assert!(!ids.contains(&1000));
+    assert_eq!(ids.iter().filter(|&&id| id == 1001).count(), 1);
+
+    let unknown = AllowedSpecial::Set(HashSet::from(["<|endofprompt|>"]));
+    assert!(matches!(tokenizer().encode_special(TEXT, &unknown), Err(TokenizerError::UnknownSpecialToken(_))));
+}
+
+
+#[test]
+fn special_ids_must_be_above_the_merges() {
+    let mut tokenizer = tokenizer();
+    assert!(matches!(tokenizer.vocab.register_special_tokens(&[("<|x|>", 100)]), Err(TokenizerError::SpecialTokenIdInUse(100))));
+    assert!(matches!(tokenizer.vocab.register_special_tokens(&[("<|x|>", 1000)]), Err(TokenizerError::SpecialTokenIdInUse(1000))));
+    assert!(tokenizer.vocab.register_special_tokens(&[("<|x|>", 1002)]).is_ok());
+    // one past the highest id, not the number of tokens
+    assert_eq!(tokenizer.vocab_size(), 1003);
+}
@@ -0,0 +1,56 @@
+use tokenizer::{BpeTokenizer, Tokenizer, TokenizerError};
+
+const TEXT: &str = "naïve café 🦀 — 東京";
+
+
+#[test]
+fn holds_back_a_character_split_across_tokens() {
+    let tokenizer = BpeTokenizer::new();
+    let mut decoder = tokenizer.stream_decoder();
+    // "é" is 0xC3 0xA9, one byte token each
+    assert_eq!(decoder.push(b'c' as u32).unwrap(), "c");
+    assert_eq!(decoder.push(0xC3).unwrap(), "");
+    assert!(decoder.has_pending());
+    assert_eq!(decoder.push(0xA9).unwrap(), "é");
+    assert!(!decoder.has_pending());
+}
+
+
+#[test]
+fn pieces_join_to_the_full_decode() {
+    let mut tokenizer = BpeTokenizer::new();
+    tokenizer.train(&TEXT.repeat(20), 280, false).unwrap();
+    let ids = tokenizer.encode(TEXT).unwrap();
+
+    let mut decoder = tokenizer.stream_decoder();
+    let mut streamed = String::new();
+    for &id in &ids {
+        streamed.push_str(&decoder.push(id).unwrap());
+    }
+    streamed.push_str(&decoder.finish());
+    assert_eq!(streamed, tokenizer.decode(&ids));
+    assert_eq!(streamed, TEXT);
+}
+
+
+#[test]
+fn invalid_bytes_are_replaced() {
+    let tokenizer = BpeTokenizer::new();
+    let mut decoder = tokenizer.stream_decoder();
+    // a lone continuation byte can never become valid
+    assert_eq!(decoder.push(0xA9).unwrap(), "\u{FFFD}");
+    assert_eq!(decoder.push(b'a' as u32).unwrap(), "a");
+    // the stream ends in the middle of "🦀"
+    assert_eq!(decoder.push(0xF0).unwrap(), "");
+    assert_eq!(decoder.push(0x9F).unwrap(), "");
+    assert_eq!(decoder.finish(), "\u{FFFD}");
+}
+
+
+#[test]
+fn unknown_ids_are_an_error() {
+    let tokenizer = BpeTokenizer::new();
+    let mut decoder = tokenizer.stream_decoder();
+    assert_eq!(decoder.push(b'a' as u32).unwrap(), "a");
+    assert!(matches!(decoder.push(300), Err(TokenizerError::UnknownToken { id: 300, position: 1 })));
+}
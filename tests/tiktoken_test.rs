@@ -0,0 +1,131 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use tokenizer::tiktoken;
+use tokenizer::{BpeTokenizer, Tokenizer, TokenizerError};
+
+// mini.tiktoken and the expected ids come from tests/fixtures/make_tiktoken_fixture.py,
+// which reimplements tiktoken's merge in Python... CL100K_IDS below are tiktoken's own
+const RANK_FILE: &str = "tests/fixtures/mini.tiktoken";
+
+/// the real rank file is too big for the repo: set CL100K_BASE to a copy of
+/// https://openaipublic.blob.core.windows.net/encodings/cl100k_base.tiktoken or put it here
+const CL100K_BASE: &str = "tests/fixtures/cl100k_base.tiktoken";
+
+/// encoded by tiktoken w/ cl100k_base:
+///     python3 -c "import tiktoken; enc = tiktoken.get_encoding('cl100k_base'); \
+///         print([enc.encode(t) for t in ['hello world', 'Hello, world!', 'tiktoken is great!']])"
+const CL100K_IDS: [(&str, &[u32]); 3] = [
+    ("hello world", &[15339, 1917]),
+    ("Hello, world!", &[9906, 11, 1917, 0]),
+    ("tiktoken is great!", &[83, 1609, 5963, 374, 2294, 0]),
+];
+
+fn expected_ids() -> Vec<u32> {
+    fs::read_to_string("tests/fixtures/tiktoken_sample_ids.txt")
+        .unwrap()
+        .split_whitespace()
+        .map(|id| id.parse().unwrap())
+        .collect()
+}
+
+
+#[test]
+fn recovers_merges_and_byte_shuffle() {
+    let vocab = tiktoken::load_tiktoken(RANK_FILE).unwrap();
+    assert_eq!(vocab.vocab_vec.len(), 300);
+    // '!' is the first printable byte so it holds rank 0, the NUL byte comes after them
+    assert_eq!(vocab.byte_token(b'!'), 0);
+    assert_eq!(vocab.byte_token(0), 188);
+    // merges are kept in rank order and every part was minted before its merge
+    for (i, &((left, right), rank)) in vocab.vocab_vec.iter().enumerate() {
+        assert_eq!(rank, 256 + i as u32);
+        assert!(left < rank && right < rank);
+    }
+    assert_eq!(vocab.stringify_word(&[256]), "er");
+    // the byte table is stored unshuffled
+    assert_eq!(vocab.token_bytes(0), Some(&b"!"[..]));
+    assert_eq!(vocab.token_bytes(256), Some(&b"er"[..]));
+    // len() is one past the highest id, cl100k's special tokens leave a gap below 100257
+    assert_eq!(vocab.len(), 556);
+    assert_eq!(tiktoken::cl100k_base(RANK_FILE).unwrap().len(), 100_277);
+}
+
+
+#[test]
+fn decodes_tiktoken_ids() {
+    let sample = fs::read_to_string("tests/fixtures/tiktoken_sample.txt").unwrap();
+    let tokenizer = BpeTokenizer::from_vocabulary(tiktoken::load_tiktoken(RANK_FILE).unwrap());
+    assert_eq!(tokenizer.decode(&expected_ids()), sample);
+}
+
+
+/// the real cl100k_base, the tests using it are ignored since the rank file is not in the repo
+fn cl100k_base() -> BpeTokenizer {
+    let path = std::env::var("CL100K_BASE").unwrap_or_else(|_| CL100K_BASE.to_string());
+    assert!(Path::new(&path).exists(), "no cl100k_base rank file at {} (see CL100K_BASE)", path);
+    BpeTokenizer::from_vocabulary(tiktoken::cl100k_base(&path).unwrap())
+}
+
+
+#[test]
+#[ignore = "needs cl100k_base.tiktoken, see CL100K_BASE"]
+fn decodes_tiktoken_cl100k_base_ids() {
+    let tokenizer = cl100k_base();
+    for (text, ids) in CL100K_IDS {
+        assert_eq!(tokenizer.decode(ids), text);
+    }
+}
+
+
+#[test]
+fn encodes_like_tiktoken() {
+    let sample = fs::read_to_string("tests/fixtures/tiktoken_sample.txt").unwrap();
+    let tokenizer = BpeTokenizer::from_vocabulary(tiktoken::load_tiktoken(RANK_FILE).unwrap());
+    assert_eq!(tokenizer.encode(&sample).unwrap(), expected_ids());
+}
+
+
+#[test]
+#[ignore = "needs cl100k_base.tiktoken, see CL100K_BASE"]
+fn encodes_like_tiktoken_cl100k_base() {
+    let tokenizer = cl100k_base();
+    for (text, ids) in CL100K_IDS {
+        assert_eq!(tokenizer.encode(text).unwrap(), ids, "{:?}", text);
+    }
+}
+
+
+#[test]
+fn duplicate_ranks_are_corrupt() {
+    let dir = common::temp_dir("tiktoken_dup");
+    let lines = fs::read_to_string(RANK_FILE).unwrap();
+    let first = lines.lines().next().unwrap();
+    let (token, rank) = first.split_once(' ').unwrap();
+
+    // the same token twice, then the same rank for two tokens
+    let path = dir.join("token.tiktoken");
+    fs::write(&path, format!("{}{} 9999\n", lines, token)).unwrap();
+    assert!(matches!(tiktoken::load_tiktoken(&path), Err(TokenizerError::CorruptModel(_))));
+    let path = dir.join("rank.tiktoken");
+    fs::write(&path, format!("{}AAAA {}\n", lines, rank)).unwrap();
+    assert!(matches!(tiktoken::load_tiktoken(&path), Err(TokenizerError::CorruptModel(_))));
+}
+
+
+#[test]
+fn recover_merges_rejects_missing_parts() {
+    // "ab" w/o the raw byte "b" is not the merge of two ranked tokens
+    let ranks = std::collections::HashMap::from([(b"a".to_vec(), 0), (b"ab".to_vec(), 1)]);
+    assert!(matches!(tiktoken::recover_merges(&ranks), Err(TokenizerError::CorruptModel(_))));
+}
+
+
+#[test]
+fn shuffled_vocabulary_cannot_be_saved() {
+    let vocab = tiktoken::load_tiktoken(RANK_FILE).unwrap();
+    let prefix = std::env::temp_dir().join("tokenizer_tiktoken_save");
+    assert!(matches!(vocab.save(&prefix.to_string_lossy()), Err(TokenizerError::Unsupported(_))));
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use std::collections::HashMap;
+use std::fs;
+
+use tokenizer::observer::{FileTraceObserver, NoObserver};
+use tokenizer::pretokenizer;
+use tokenizer::training::{self, Verbosity};
+use tokenizer::{BpeTokenizer, TrainerConfig, Tokenizer, TokenizerError, Vocabulary};
+
+/// the merges as the trace in data/output/train_target.txt shows them
+fn trace_lines(tokenizer: &BpeTokenizer) -> Vec<String> {
+    let vocab = &tokenizer.vocab;
+    vocab.vocab_vec
+        .iter()
+        .map(|&((byte1, byte2), new_word)| format!("[{}][{}] -> [{}] {}",
+            vocab.stringify_word(&[byte1]),
+            vocab.stringify_word(&[byte2]),
+            vocab.stringify_word(&[byte1, byte2]),
+            new_word))
+        .collect()
+}
+
+
+#[test]
+fn incremental_training_matches_recorded_merges() {
+    // recorded by the original trainer that recounted every pair on each merge
+    let expected = include_str!("../data/output/train_target.txt");
+    let text = fs::read_to_string("data/train_text.txt").unwrap();
+
+    let mut tokenizer = BpeTokenizer::new();
+    tokenizer.train(&text, 512, false).unwrap();
+
+    assert_eq!(trace_lines(&tokenizer), expected.lines().collect::<Vec<_>>());
+}
+
+
+#[test]
+fn weighted_unique_chunks_match_every_occurrence() {
+    let text = fs::read_to_string("data/train_text.txt").unwrap();
+    let mut tokenizer = BpeTokenizer::new();
+    tokenizer.train(&text, 512, false).unwrap();
+
+    let chunk_counts = training::count_chunks(&text, &TrainerConfig::default());
+    assert!(chunk_counts.len() < pretokenizer::gpt4().split(&text).len());
+    let weighted = training::train_from_chunk_counts(&chunk_counts, &TrainerConfig::default(), &mut NoObserver).unwrap();
+
+    assert_eq!(weighted.vocab_vec, tokenizer.vocab.vocab_vec);
+}
+
+
+#[test]
+fn weights_count_as_repeated_chunks() {
+    // " ab" twice outweighs " cd" once even though each is a single distinct chunk
+    let chunk_counts: HashMap<String, u64> =
+        [(" ab".to_string(), 2), (" cd".to_string(), 1)].into_iter().collect();
+    let config = TrainerConfig { vocab_size: 258, ..TrainerConfig::default() };
+    let vocab = training::train_from_chunk_counts(&chunk_counts, &config, &mut NoObserver).unwrap();
+    assert_eq!(vocab.vocab_vec, vec![((32, 97), 256), ((256, 98), 257)]);
+}
+
+
+#[test]
+fn config_limits_merges() {
+    let text = "aaaa bbbb aaaa cccccc";
+    let quiet = TrainerConfig { vocab_size: 300, verbosity: Verbosity::Quiet, ..TrainerConfig::default() };
+    let chunk_counts = training::count_chunks(text, &quiet);
+
+    // tokens of at most 2 bytes: "aa", "bb", "cc" and then nothing longer
+    let short = TrainerConfig { max_token_length: Some(2), ..quiet.clone() };
+    let vocab = training::train_from_chunk_counts(&chunk_counts, &short, &mut NoObserver).unwrap();
+    assert_eq!(vocab.vocab_vec, vec![((97, 97), 256), ((99, 99), 257), ((98, 98), 258)]);
+
+    // only pairs seen at least 5 times: "aa" (3 in each "aaaa" chunk) and "cc" (5)
+    let frequent = TrainerConfig { min_pair_frequency: 5, ..quiet.clone() };
+    let vocab = training::train_from_chunk_counts(&chunk_counts, &frequent, &mut NoObserver).unwrap();
+    assert_eq!(vocab.vocab_vec, vec![((97, 97), 256), ((99, 99), 257)]);
+}
+
+
+#[test]
+fn config_adds_special_tokens_after_merges() {
+    let config = TrainerConfig {
+        vocab_size: 260,
+        verbosity: Verbosity::Quiet,
+        pre_tokenizer: pretokenizer::none(),
+        special_tokens: vec!["<|endoftext|>".to_string()],
+        ..TrainerConfig::default()
+    };
+    // no pre-tokenizer: pairs across the spaces are counted too
+    let chunk_counts = training::count_chunks("ab ab ab ab", &config);
+    let vocab = training::train_from_chunk_counts(&chunk_counts, &config, &mut NoObserver).unwrap();
+    assert_eq!(vocab.vocab_vec.len(), 3);
+    assert_eq!(vocab.special_tokens, vec![("<|endoftext|>".to_string(), 259)]);
+    assert_eq!(vocab.pre_tokenizer.pattern(), "");
+    assert_eq!(vocab.len(), 260);
+    assert!(vocab.has_merges() && !vocab.is_empty());
+    // the raw bytes alone are not an empty vocabulary either
+    assert!(!Vocabulary::new().has_merges() && !Vocabulary::new().is_empty());
+}
+
+
+#[test]
+fn config_rejects_invalid_special_tokens() {
+    for special_tokens in [vec![""], vec!["<|end of text|>"], vec!["<|endoftext|>", "<|endoftext|>"]] {
+        let config = TrainerConfig {
+            vocab_size: 260,
+            verbosity: Verbosity::Quiet,
+            special_tokens: special_tokens.iter().map(|special| special.to_string()).collect(),
+            ..TrainerConfig::default()
+        };
+        let chunk_counts = training::count_chunks("ab ab ab ab", &config);
+        assert!(matches!(training::train_from_chunk_counts(&chunk_counts, &config, &mut NoObserver),
+            Err(TokenizerError::InvalidSpecialToken(_))), "{:?}", special_tokens);
+        assert!(matches!(training::train_tokenizer("ab ab ab ab", &config, &mut NoObserver),
+            Err(TokenizerError::InvalidSpecialToken(_))), "{:?}", special_tokens);
+    }
+}
+
+
+#[test]
+fn file_observer_reproduces_trace_files() {
+    let dir = common::temp_dir("trace");
+    let text = fs::read_to_string("data/train_text.txt").unwrap();
+
+    let mut trace = FileTraceObserver::create(&dir).unwrap();
+    let mut tokenizer = BpeTokenizer::new();
+    tokenizer.train_with_config(&text, &TrainerConfig::default(), &mut trace).unwrap();
+    trace.finish().unwrap();
+
+    assert_eq!(fs::read_to_string(dir.join("train_output.txt")).unwrap(), include_str!("../data/output/train_target.txt"));
+    assert_eq!(fs::read_to_string(dir.join("chunk_output.txt")).unwrap(), include_str!("../data/output/chunk_target.txt"));
+    fs::remove_dir_all(&dir).unwrap();
+}